@@ -24,10 +24,11 @@ fn main() {
     let options = PrinterJobOptions {
         name: Some("Rust file print job"),
         raw_properties: &[("copies", "1")],
+        ..PrinterJobOptions::none()
     };
 
     match printer.print_file(file_path, options) {
-        Ok(job_id) => println!("Print file submitted, job_id={job_id}"),
+        Ok(submission) => println!("Print file submitted, job_id={}", submission.job_id),
         Err(err) => eprintln!("Failed to print file: {err}"),
     }
 }
@@ -56,14 +56,12 @@ fn main() {
         print_height
     );
 
-    match printer.print_image(
-        image,
-        Some("Rust image print job"),
-        1,
-        None,
-        None,
-    ) {
-        Ok(job_id) => println!("Image print submitted, job_id={job_id}"),
+    match printer.print_image(image, Some("Rust image print job"), 1, None, None) {
+        Ok(result) => println!(
+            "Image print submitted, job_id={}, pages={}",
+            result.job_id,
+            result.pages_printed.unwrap_or_default()
+        ),
         Err(err) => eprintln!("Failed to print image: {err}"),
     }
 }
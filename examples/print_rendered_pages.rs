@@ -0,0 +1,78 @@
+use std::env;
+
+use image::{DynamicImage, Rgb, RgbImage};
+use printers::get_printer_by_name;
+use printers::render::{device_size_px, page_size_mm, print_images};
+
+// A4 rendered at 150 DPI
+const RENDER_DPI: f64 = 150.0;
+const PAGE_WIDTH_PX: u32 = 1240;
+const PAGE_HEIGHT_PX: u32 = 1754;
+
+fn main() {
+    // Usage:
+    // cargo run --example print_rendered_pages --features render -- "Your Printer Name" 2
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <printer_name> <page_count>", args[0]);
+        return;
+    }
+
+    let Some(printer) = get_printer_by_name(&args[1]) else {
+        eprintln!("Printer not found: {}", args[1]);
+        return;
+    };
+    let page_count: u32 = match args[2].parse() {
+        Ok(count) if count > 0 => count,
+        _ => {
+            eprintln!("Page count must be a number greater than 0: {}", args[2]);
+            return;
+        }
+    };
+
+    // Stand-in for pages rasterized from a printpdf document
+    let pages: Vec<DynamicImage> = (0..page_count).map(render_page).collect();
+
+    let caps = printer.get_printer_caps();
+    let (width_mm, height_mm) = page_size_mm(&pages[0], RENDER_DPI);
+    let (width_px, height_px) = device_size_px(&pages[0], RENDER_DPI, &caps);
+    println!(
+        "Page: {width_mm:.1}x{height_mm:.1} mm, {width_px}x{height_px} px at {}x{} DPI",
+        caps.dpi_x, caps.dpi_y
+    );
+
+    match print_images(&printer, pages, RENDER_DPI, Some("Rendered document")) {
+        Ok(results) => {
+            for result in results {
+                println!(
+                    "job_id={}, pages={}",
+                    result.job_id,
+                    result.pages_printed.unwrap_or_default()
+                );
+            }
+        }
+        Err(err) => eprintln!("Failed to print pages: {err}"),
+    }
+}
+
+fn render_page(index: u32) -> DynamicImage {
+    let mut page = RgbImage::from_pixel(PAGE_WIDTH_PX, PAGE_HEIGHT_PX, Rgb([255, 255, 255]));
+    // Draw a frame one inch from the edges and a marker per page number
+    let margin = RENDER_DPI as u32;
+    for x in margin..PAGE_WIDTH_PX - margin {
+        page.put_pixel(x, margin, Rgb([0, 0, 0]));
+        page.put_pixel(x, PAGE_HEIGHT_PX - margin, Rgb([0, 0, 0]));
+    }
+    for y in margin..PAGE_HEIGHT_PX - margin {
+        page.put_pixel(margin, y, Rgb([0, 0, 0]));
+        page.put_pixel(PAGE_WIDTH_PX - margin, y, Rgb([0, 0, 0]));
+    }
+    for i in 0..=index {
+        for x in 0..20 {
+            for y in 0..20 {
+                page.put_pixel(margin + 20 + i * 30 + x, margin + 20 + y, Rgb([0, 0, 0]));
+            }
+        }
+    }
+    DynamicImage::ImageRgb8(page)
+}
@@ -11,7 +11,7 @@ fn main() {
 
     let content = b"Hello from rust-printers!\n";
     match printer.print(content, PrinterJobOptions::none()) {
-        Ok(job_id) => println!("Print job submitted, job_id={job_id}"),
+        Ok(submission) => println!("Print job submitted, job_id={}", submission.job_id),
         Err(err) => eprintln!("Failed to print: {err}"),
     }
 }
@@ -1,2 +1,3 @@
 pub mod base;
+pub mod time;
 pub mod traits;
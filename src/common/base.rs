@@ -1,3 +1,12 @@
+pub mod content;
+pub mod dialog;
+pub mod image;
 pub mod job;
+pub mod label;
+pub mod media;
 pub mod options;
+pub mod permissions;
 pub mod printer;
+pub mod registry;
+pub mod session;
+pub mod watcher;
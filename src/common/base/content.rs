@@ -0,0 +1,29 @@
+use image::DynamicImage;
+use std::path::PathBuf;
+
+/**
+ * Anything that can be printed with Printer::submit
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintContent {
+    /**
+     * Bytes sent like print, in the format given by the job options
+     */
+    Raw(Vec<u8>),
+    /**
+     * File sent like print_file
+     */
+    File(PathBuf),
+    /**
+     * Image printed like print_image_with_options
+     */
+    Image(DynamicImage),
+    /**
+     * Images printed one job per image, in order
+     */
+    Images(Vec<DynamicImage>),
+    /**
+     * Plain text, sent as text unless the job options give another format
+     */
+    Text(String),
+}
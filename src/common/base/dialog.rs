@@ -0,0 +1,81 @@
+use crate::common::base::{
+    media::{FormInfo, FormMargins, MediaSize},
+    printer::{PRINTER_NOT_FOUND, Printer},
+};
+use crate::common::traits::platform::Unit;
+
+/**
+ * Everything a print dialog shows for a printer, with sizes converted to millimeters
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintDialogModel {
+    pub printer_name: String,
+    pub dpi_x: i32,
+    pub dpi_y: i32,
+    /**
+     * Size of the current paper, None when the printer does not report its device caps
+     */
+    pub page_size: Option<MediaSize>,
+    /**
+     * Unprintable margins of the current paper (zero when unknown)
+     */
+    pub margins: FormMargins,
+    /**
+     * Maximum number of copies, None when the printer does not report it
+     */
+    pub max_copies: Option<u32>,
+    pub duplex: bool,
+    pub collate: bool,
+    /**
+     * Papers the printer can be asked for by name (spooler forms, empty on unix)
+     */
+    pub papers: Vec<FormInfo>,
+}
+
+impl PrintDialogModel {
+    /**
+     * Collect the dialog model of a printer, looked up like get_printer_by_name
+     */
+    pub fn from_printer(printer_name: &str) -> Result<Self, &'static str> {
+        let printer = crate::get_printer_by_name(printer_name).ok_or(PRINTER_NOT_FOUND)?;
+        Ok(Self::for_printer(&printer))
+    }
+
+    /**
+     * Collect the dialog model of an already resolved printer
+     */
+    pub fn for_printer(printer: &Printer) -> Self {
+        let caps = printer.get_printer_caps();
+        let job_caps = printer.get_job_capabilities();
+
+        let (page_size, margins) = if caps.dpi_x > 0 && caps.dpi_y > 0 {
+            let mm = caps.in_units(Unit::Mm);
+            (
+                Some(MediaSize {
+                    width_mm: mm.page_width,
+                    height_mm: mm.page_height,
+                }),
+                FormMargins {
+                    top_mm: mm.margin_top,
+                    right_mm: mm.margin_right,
+                    bottom_mm: mm.margin_bottom,
+                    left_mm: mm.margin_left,
+                },
+            )
+        } else {
+            (None, FormMargins::default())
+        };
+
+        PrintDialogModel {
+            printer_name: printer.system_name.clone(),
+            dpi_x: caps.dpi_x,
+            dpi_y: caps.dpi_y,
+            page_size,
+            margins,
+            max_copies: job_caps.max_copies,
+            duplex: job_caps.duplex,
+            collate: job_caps.collate,
+            papers: printer.get_forms(),
+        }
+    }
+}
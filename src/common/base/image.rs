@@ -0,0 +1,361 @@
+use image::{DynamicImage, GrayImage, RgbImage};
+
+/**
+ * Default page background, white
+ */
+pub const WHITE: [u8; 3] = [255, 255, 255];
+
+/**
+ * How print_image places the image on the page
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScaleMode {
+    /**
+     * Scale the image to the largest size that fits the printable area, keeping its aspect ratio
+     */
+    #[default]
+    Fit,
+    /**
+     * Print the image at its native pixel size, centered horizontally at the top of the page
+     * (behavior before Fit became the default)
+     */
+    Center,
+    /**
+     * Print every image pixel as one device pixel from the top left corner of the printable
+     * area, without resampling, for images pre-rendered at the printer DPI. Parts outside
+     * the printable area are clipped (on CUPS the image is placed like Center)
+     */
+    None,
+}
+
+impl ScaleMode {
+    /**
+     * Return the destination rect (x, y, width, height) of an image inside an area, in device units
+     */
+    pub fn target_rect(
+        &self,
+        image_width: u32,
+        image_height: u32,
+        area_width: i32,
+        area_height: i32,
+    ) -> (i32, i32, i32, i32) {
+        match self {
+            ScaleMode::Fit => {
+                if image_width == 0 || image_height == 0 || area_width <= 0 || area_height <= 0 {
+                    return (0, 0, 0, 0);
+                }
+
+                let scale = (area_width as f64 / image_width as f64)
+                    .min(area_height as f64 / image_height as f64);
+                let width = ((image_width as f64 * scale).round() as i32).clamp(1, area_width);
+                let height = ((image_height as f64 * scale).round() as i32).clamp(1, area_height);
+
+                (
+                    (area_width - width) / 2,
+                    (area_height - height) / 2,
+                    width,
+                    height,
+                )
+            }
+            ScaleMode::Center => (
+                (area_width - image_width as i32) / 2,
+                0,
+                image_width as i32,
+                image_height as i32,
+            ),
+            ScaleMode::None => (0, 0, image_width as i32, image_height as i32),
+        }
+    }
+}
+
+/**
+ * How the image is resampled when stretched to the page, trading quality for speed
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StretchMode {
+    /**
+     * Average the source pixels, best quality but slowest for large bitmaps.
+     * Drivers that do not support it get ColorOnColor
+     */
+    #[default]
+    Halftone,
+    /**
+     * Drop eliminated pixels, fast and suitable for color images
+     */
+    ColorOnColor,
+    /**
+     * Keep black pixels over white ones, fast and suitable for monochrome labels
+     */
+    BlackOnWhite,
+}
+
+/**
+ * Rectangle in pixels (source image) or device units (page), from the top left corner
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /**
+     * Return true if the rect is not empty and lies entirely inside an area of `width` x `height`
+     */
+    pub fn is_within(&self, width: u32, height: u32) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self
+                .x
+                .checked_add(self.width)
+                .is_some_and(|right| right <= width)
+            && self
+                .y
+                .checked_add(self.height)
+                .is_some_and(|bottom| bottom <= height)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PrintImageOptions<'a> {
+    /**
+     * Visual name/title of the job (same length limits as PrinterJobOptions::name)
+     */
+    pub name: Option<&'a str>,
+    /**
+     * Number of pages (copies of the image) to print
+     */
+    pub page_count: u32,
+    /**
+     * Paper width in millimeters
+     */
+    pub print_width: Option<f64>,
+    /**
+     * Paper height in millimeters
+     */
+    pub print_height: Option<f64>,
+    /**
+     * Name of a spooler form to print on, like "A4", see Printer::get_forms.
+     * Takes precedence over print_width and print_height (only used on Windows)
+     */
+    pub form: Option<&'a str>,
+    /**
+     * How the image is placed on the page (default is Fit)
+     */
+    pub scale_mode: ScaleMode,
+    /**
+     * Part of the image to print, in image pixels (default is the whole image)
+     */
+    pub src_rect: Option<Rect>,
+    /**
+     * Exact position and size of the image in device units from the top left corner of the
+     * printable area, overriding scale_mode, like to print onto pre-printed forms
+     * (only used on Windows, see Printer::get_printer_caps)
+     */
+    pub dest_rect: Option<Rect>,
+    /**
+     * How the image is resampled when stretched (default is Halftone, only used on Windows)
+     */
+    pub stretch_mode: StretchMode,
+    /**
+     * RGB color transparent pixels are flattened onto. On Windows the page area not
+     * covered by the image is also filled with it (default is white)
+     */
+    pub background: [u8; 3],
+    /**
+     * Path of the file the printer driver writes its output to instead of printing,
+     * like the PDF of "Microsoft Print to PDF" (only supported on Windows)
+     */
+    pub output_file: Option<&'a str>,
+}
+
+impl PrintImageOptions<'_> {
+    pub fn none() -> Self {
+        PrintImageOptions {
+            name: None,
+            page_count: 1,
+            print_width: None,
+            print_height: None,
+            form: None,
+            scale_mode: ScaleMode::default(),
+            src_rect: None,
+            dest_rect: None,
+            stretch_mode: StretchMode::default(),
+            background: WHITE,
+            output_file: None,
+        }
+    }
+}
+
+/**
+ * How print_image_tiled splits an image larger than the page across several sheets
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct TileOptions<'a> {
+    /**
+     * Visual name/title of the jobs, one per tile
+     */
+    pub name: Option<&'a str>,
+    /**
+     * Resolution the image was made for, which sets its physical size
+     */
+    pub source_dpi: f64,
+    /**
+     * Width in millimeters printed again at the start of the next tile, to glue the sheets
+     */
+    pub overlap_mm: f64,
+    /**
+     * Draw marks at the corners of the tile edges where the next tile starts
+     */
+    pub crop_marks: bool,
+}
+
+impl TileOptions<'_> {
+    pub fn new(source_dpi: f64) -> Self {
+        TileOptions {
+            name: None,
+            source_dpi,
+            overlap_mm: 0.0,
+            crop_marks: false,
+        }
+    }
+}
+
+/**
+ * Split an image into tiles of `tile_width` x `tile_height` pixels, row by row, each one
+ * starting `overlap` pixels before the end of the previous one. Edge tiles are padded with
+ * white so every tile has the same scale, crop marks are `mark_length` pixels long
+ */
+pub(crate) fn tile_image(
+    image: DynamicImage,
+    (tile_width, tile_height): (u32, u32),
+    (overlap_x, overlap_y): (u32, u32),
+    mark_length: Option<u32>,
+) -> Vec<DynamicImage> {
+    let image = flatten_alpha(image, WHITE).to_rgb8();
+    let (step_x, step_y) = (tile_width - overlap_x, tile_height - overlap_y);
+    let count = |size: u32, tile: u32, step: u32| 1 + size.saturating_sub(tile).div_ceil(step);
+    let columns = count(image.width(), tile_width, step_x);
+    let rows = count(image.height(), tile_height, step_y);
+
+    let mut tiles = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x, y) = (column * step_x, row * step_y);
+            let part = image::imageops::crop_imm(
+                &image,
+                x,
+                y,
+                tile_width.min(image.width() - x),
+                tile_height.min(image.height() - y),
+            )
+            .to_image();
+
+            let mut tile = RgbImage::from_pixel(tile_width, tile_height, image::Rgb(WHITE));
+            image::imageops::replace(&mut tile, &part, 0, 0);
+
+            if let Some(length) = mark_length {
+                let black = image::Rgb([0, 0, 0]);
+                // The next tile starts where the overlap starts, or at the edge without overlap
+                if column + 1 < columns {
+                    let cut = step_x.min(tile_width - 1);
+                    for y in (0..length).chain(tile_height.saturating_sub(length)..tile_height) {
+                        tile.put_pixel(cut, y.min(tile_height - 1), black);
+                    }
+                }
+                if row + 1 < rows {
+                    let cut = step_y.min(tile_height - 1);
+                    for x in (0..length).chain(tile_width.saturating_sub(length)..tile_width) {
+                        tile.put_pixel(x.min(tile_width - 1), cut, black);
+                    }
+                }
+            }
+
+            tiles.push(DynamicImage::ImageRgb8(tile));
+        }
+    }
+
+    tiles
+}
+
+/**
+ * Draw a diagnostic page of `width` x `height` pixels: a border, a crosshair through the
+ * center and rulers with a tick every millimeter along the top and left edges
+ */
+pub(crate) fn test_page_image(width: u32, height: u32, px_per_mm: f64) -> DynamicImage {
+    let mut page = GrayImage::from_pixel(width, height, image::Luma([255]));
+    let black = image::Luma([0]);
+
+    for x in 0..width {
+        for y in [0, height / 2, height - 1] {
+            page.put_pixel(x, y, black);
+        }
+    }
+    for y in 0..height {
+        for x in [0, width / 2, width - 1] {
+            page.put_pixel(x, y, black);
+        }
+    }
+
+    // Ticks are 1mm long, 2mm every 5mm and 4mm every 10mm
+    let tick_length = |mm: u32| -> u32 {
+        let length = match mm {
+            mm if mm % 10 == 0 => 4.0,
+            mm if mm % 5 == 0 => 2.0,
+            _ => 1.0,
+        };
+        (length * px_per_mm).round() as u32
+    };
+    for mm in 1.. {
+        let offset = (mm as f64 * px_per_mm).round() as u32;
+        if offset >= width && offset >= height {
+            break;
+        }
+        let length = tick_length(mm);
+        if offset < width {
+            for y in 0..length.min(height) {
+                page.put_pixel(offset, y, black);
+            }
+        }
+        if offset < height {
+            for x in 0..length.min(width) {
+                page.put_pixel(x, offset, black);
+            }
+        }
+    }
+
+    DynamicImage::ImageLuma8(page)
+}
+
+/**
+ * Composite an image with alpha over a solid background, keeping grayscale images
+ * grayscale when the background is gray. Images without alpha are returned as is
+ */
+pub(crate) fn flatten_alpha(image: DynamicImage, background: [u8; 3]) -> DynamicImage {
+    if !image.color().has_alpha() {
+        return image;
+    }
+
+    let blend = |value: u8, alpha: u8, background: u8| -> u8 {
+        ((value as u32 * alpha as u32 + background as u32 * (255 - alpha as u32)) / 255) as u8
+    };
+
+    let [r, g, b] = background;
+    if !image.color().has_color() && r == g && g == b {
+        let source = image.to_luma_alpha8();
+        let flat = GrayImage::from_fn(source.width(), source.height(), |x, y| {
+            let [l, a] = source.get_pixel(x, y).0;
+            image::Luma([blend(l, a, r)])
+        });
+        return DynamicImage::ImageLuma8(flat);
+    }
+
+    let source = image.to_rgba8();
+    let flat = RgbImage::from_fn(source.width(), source.height(), |x, y| {
+        let [pr, pg, pb, a] = source.get_pixel(x, y).0;
+        image::Rgb([blend(pr, a, r), blend(pg, a, g), blend(pb, a, b)])
+    });
+    DynamicImage::ImageRgb8(flat)
+}
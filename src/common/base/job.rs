@@ -1,20 +1,35 @@
 use std::{
-    fmt::{Debug, Error, Formatter},
-    time::SystemTime,
+    fmt::{Debug, Display, Error, Formatter},
+    ops::Range,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use crate::common::base::image::PrintImageOptions;
+use crate::common::base::printer::{PRINTER_NOT_FOUND, Printer};
 use crate::common::traits::platform::{PlatformActions, PlatformPrinterJobGetters};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PrinterJobState {
     PENDING,
     PAUSED,
+    /**
+     * The application is still writing the job to the spooler (Windows only)
+     */
+    SPOOLING,
     PROCESSING,
+    /**
+     * The job was cancelled, or aborted by CUPS after an error
+     */
     CANCELLED,
+    /**
+     * The job was printed (JOB_STATUS_PRINTED or JOB_STATUS_COMPLETE on Windows, also while
+     * it is being deleted or retained), a terminal state for wait_for_job
+     */
     COMPLETED,
     UNKNOWN,
 }
 
+#[derive(Clone, PartialEq)]
 pub struct PrinterJob {
     /**
      * Job ID
@@ -29,42 +44,90 @@ pub struct PrinterJob {
      */
     pub state: PrinterJobState,
     /**
-     * Indicates the job file type, ex application/pdf
+     * Raw format of the spooled data as reported by the platform: the MIME type on CUPS
+     * (ex application/pdf), the spool datatype on Windows (ex RAW). Not the paper type,
+     * kept for compatibility, see data_type
      */
     pub media_type: String,
+    /**
+     * Format of the spooled data, parsed from media_type
+     */
+    pub data_type: DataType,
     /**
      * Date when a job was created
      */
     pub created_at: SystemTime,
     /**
-     * Date when a job was processed or started printing
+     * Date when a job was processed or started printing, None on Windows
      */
     pub processed_at: Option<SystemTime>,
     /**
-     * Date when a job was completed
+     * Date when a job was completed, None on Windows
      */
     pub completed_at: Option<SystemTime>,
     /**
      * Name of printer
      */
     pub printer_name: String,
+    /**
+     * Name of the user who submitted the job
+     */
+    pub user: String,
+    /**
+     * Queue priority of the job, higher values are printed first
+     */
+    pub priority: u32,
+    /**
+     * Size of the spooled job data in bytes
+     */
+    pub size: u64,
+    /**
+     * Next job of a chained job (Windows JOB_INFO_3W), None if the job is not chained
+     */
+    pub next_job_id: Option<u64>,
 }
 
 impl PrinterJob {
     pub(crate) fn from_platform_printer_job_getters(
         platform_printer_job: &dyn PlatformPrinterJobGetters,
     ) -> Self {
+        let media_type = platform_printer_job.get_media_type();
         PrinterJob {
             id: platform_printer_job.get_id(),
             name: platform_printer_job.get_name(),
             state: PrinterJobState::from_platform_state(platform_printer_job.get_state()),
-            media_type: platform_printer_job.get_media_type(),
+            data_type: DataType::parse(&media_type),
+            media_type,
             created_at: platform_printer_job.get_created_at(),
             processed_at: platform_printer_job.get_processed_at(),
             completed_at: platform_printer_job.get_completed_at(),
             printer_name: platform_printer_job.get_printer(),
+            user: platform_printer_job.get_user(),
+            priority: platform_printer_job.get_priority(),
+            size: platform_printer_job.get_size(),
+            next_job_id: None,
         }
     }
+
+    /**
+     * Time since the job was created. A created_at in the future (clock skew)
+     * counts as zero
+     */
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.created_at)
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /**
+     * Return true if the job is still pending, spooling or processing after `threshold`
+     */
+    pub fn is_stuck(&self, threshold: Duration) -> bool {
+        matches!(
+            self.state,
+            PrinterJobState::PENDING | PrinterJobState::SPOOLING | PrinterJobState::PROCESSING
+        ) && self.age() > threshold
+    }
 }
 
 impl Debug for PrinterJob {
@@ -76,40 +139,915 @@ impl Debug for PrinterJob {
                 \r  name: {:?},
                 \r  state: {:?},
                 \r  media_type: {:?},
+                \r  data_type: {:?},
                 \r  created_at: {:?},
                 \r  processed_at: {:?},
                 \r  completed_at: {:?},
                 \r  printer_name: {:?},
+                \r  user: {:?},
+                \r  priority: {:?},
+                \r  size: {:?},
+                \r  next_job_id: {:?},
             \r}}",
             self.id,
             self.name,
             self.state,
             self.media_type,
+            self.data_type,
             self.created_at,
             self.processed_at,
             self.completed_at,
             self.printer_name,
+            self.user,
+            self.priority,
+            self.size,
+            self.next_job_id,
         )
     }
 }
 
 impl PrinterJobState {
-    pub(crate) fn from_platform_state(platform_state: u64) -> Self {
+    /**
+     * Convert a raw platform job state (JOB_INFO.Status on Windows, job-state on CUPS)
+     * into a PrinterJobState
+     */
+    pub fn from_platform_state(platform_state: u64) -> Self {
         crate::Platform::parse_printer_job_state(platform_state)
     }
+
+    /**
+     * Return the SetJobW JOB_CONTROL_* command that moves a job into this state
+     */
+    pub fn to_windows_command(&self) -> Option<u64> {
+        match self {
+            PrinterJobState::PAUSED => Some(1),
+            PrinterJobState::PROCESSING => Some(2),
+            PrinterJobState::PENDING => Some(4),
+            PrinterJobState::CANCELLED => Some(5),
+            _ => None,
+        }
+    }
+
+    /**
+     * Return true if the job reached a final state and will not change anymore
+     */
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            PrinterJobState::COMPLETED | PrinterJobState::CANCELLED
+        )
+    }
+
+    /**
+     * Return true if the job ended without being printed, which is CANCELLED once
+     * cancelled or aborted. A Windows job blocked by JOB_STATUS_ERROR has not ended, it
+     * stays PENDING until it is restarted or deleted, the printer state tells it is blocked
+     */
+    pub fn is_error(&self) -> bool {
+        *self == PrinterJobState::CANCELLED
+    }
+}
+
+impl Display for PrinterJobState {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let text = match self {
+            PrinterJobState::PENDING => "Pending",
+            PrinterJobState::PAUSED => "Paused",
+            PrinterJobState::SPOOLING => "Spooling",
+            PrinterJobState::PROCESSING => "Processing",
+            PrinterJobState::CANCELLED => "Cancelled",
+            PrinterJobState::COMPLETED => "Completed",
+            PrinterJobState::UNKNOWN => "Unknown",
+        };
+        write!(fmt, "{text}")
+    }
+}
+
+/**
+ * Ordering applied to a job listing after it is fetched
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum JobSortOrder {
+    /**
+     * Keep the order reported by the spooler
+     */
+    #[default]
+    Spooler,
+    /**
+     * Oldest jobs first
+     */
+    BySubmittedTime,
+    /**
+     * Highest priority first, then oldest first
+     */
+    ByPriority,
+    /**
+     * Lowest job id first
+     */
+    ById,
+}
+
+impl JobSortOrder {
+    /**
+     * Sort jobs in place, keeping the spooler order for equal keys
+     */
+    pub fn sort(&self, jobs: &mut [PrinterJob]) {
+        match self {
+            JobSortOrder::Spooler => {}
+            JobSortOrder::BySubmittedTime => jobs.sort_by_key(|j| j.created_at),
+            JobSortOrder::ByPriority => jobs.sort_by(|a, b| {
+                b.priority
+                    .cmp(&a.priority)
+                    .then(a.created_at.cmp(&b.created_at))
+            }),
+            JobSortOrder::ById => jobs.sort_by_key(|j| j.id),
+        }
+    }
+}
+
+/**
+ * What to do when a requested job option is not supported by the printer
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OnUnsupported {
+    /**
+     * Lower the option to what the printer supports
+     */
+    #[default]
+    Clamp,
+    /**
+     * Refuse to print
+     */
+    Error,
+}
+
+/**
+ * What to do when the document to print is empty
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OnEmpty {
+    /**
+     * Refuse to print, some printers output a blank or garbage page for empty jobs
+     */
+    #[default]
+    Error,
+    /**
+     * Succeed without spooling anything, the returned job id is 0
+     */
+    Skip,
+}
+
+impl OnEmpty {
+    /**
+     * Return the result of an empty document, the same for buffers, files and readers
+     */
+    pub(crate) fn empty_result(&self) -> Result<PrintResult, &'static str> {
+        match self {
+            OnEmpty::Error => Err("Nothing to print, the document is empty"),
+            OnEmpty::Skip => Ok(PrintResult {
+                job_id: 0,
+                pages_printed: Some(0),
+                error: None,
+            }),
+        }
+    }
+}
+
+/**
+ * Format of the document sent to the printer
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentFormat {
+    /**
+     * Printer native data (PCL, ESC/POS, ZPL...), sent without processing
+     */
+    Raw,
+    Text,
+    Pdf,
+    PostScript,
+    Xps,
+    Png,
+    Jpeg,
+    /**
+     * Detect the format from the content magic bytes (PDF, PostScript, PNG, JPEG).
+     * CUPS receives the detected MIME type, or application/octet-stream to let it
+     * auto-type unknown content. Windows picks the matching datatype
+     */
+    Auto,
+}
+
+impl DocumentFormat {
+    /**
+     * Detect the format from the first bytes of a document
+     */
+    pub fn detect(buffer: &[u8]) -> Option<DocumentFormat> {
+        if buffer.starts_with(b"%PDF") {
+            Some(DocumentFormat::Pdf)
+        } else if buffer.starts_with(b"%!PS") {
+            Some(DocumentFormat::PostScript)
+        } else if buffer.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(DocumentFormat::Png)
+        } else if buffer.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(DocumentFormat::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Resolve Auto to a detected format, keeping Auto when nothing matches
+     */
+    pub fn resolve(&self, buffer: &[u8]) -> DocumentFormat {
+        match self {
+            DocumentFormat::Auto => DocumentFormat::detect(buffer).unwrap_or(DocumentFormat::Auto),
+            format => *format,
+        }
+    }
+
+    /**
+     * Return the winspool datatype
+     */
+    pub fn windows_datatype(&self) -> &'static str {
+        match self {
+            DocumentFormat::Text => "TEXT",
+            DocumentFormat::Xps => "XPS_PASS",
+            _ => "RAW",
+        }
+    }
+
+    /**
+     * Return the MIME type used as CUPS document-format
+     */
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            DocumentFormat::Raw => "application/vnd.cups-raw",
+            DocumentFormat::Text => "text/plain",
+            DocumentFormat::Pdf => "application/pdf",
+            DocumentFormat::PostScript => "application/postscript",
+            DocumentFormat::Xps => "application/vnd.ms-xpsdocument",
+            DocumentFormat::Png => "image/png",
+            DocumentFormat::Jpeg => "image/jpeg",
+            DocumentFormat::Auto => "application/octet-stream",
+        }
+    }
+}
+
+/**
+ * How much detail job listings ask the spooler for
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JobInfoLevel {
+    /**
+     * Id, name, state, printer, user, priority, data type and creation time only. On Windows
+     * (JOB_INFO_1W) size is 0 and next_job_id is None
+     */
+    Basic,
+    /**
+     * Adds size and next_job_id (JOB_INFO_2W and JOB_INFO_3W on Windows), always used by
+     * CUPS. processed_at and completed_at are only filled by CUPS, Windows spoolers do not
+     * keep those times at any level
+     */
+    #[default]
+    Detailed,
+}
+
+/**
+ * Format of the data spooled for a job, from the Windows datatype (JOB_INFO.pDatatype)
+ * or the CUPS document-format
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataType {
+    /**
+     * Printer native data, sent without processing
+     */
+    Raw,
+    /**
+     * Enhanced metafile spooled by GDI applications (Windows only)
+     */
+    Emf,
+    Text,
+    /**
+     * XPS document passed through to the printer
+     */
+    XpsPass,
+    /**
+     * Any other datatype or MIME type, as reported
+     */
+    Other(String),
+}
+
+impl DataType {
+    /**
+     * Parse a Windows datatype or a CUPS MIME type
+     */
+    pub fn parse(value: &str) -> DataType {
+        let upper = value.trim().to_ascii_uppercase();
+        match upper.as_str() {
+            "RAW" | "RAW [FF APPENDED]" | "RAW [FF AUTO]" | "APPLICATION/VND.CUPS-RAW" => {
+                DataType::Raw
+            }
+            emf if emf.starts_with("NT EMF") => DataType::Emf,
+            "TEXT" | "TEXT/PLAIN" => DataType::Text,
+            "XPS_PASS" => DataType::XpsPass,
+            _ => DataType::Other(value.to_string()),
+        }
+    }
+}
+
+/**
+ * Chunking of the data written with WritePrinter
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamOptions {
+    /**
+     * Bytes sent per WritePrinter call (default 64KB)
+     */
+    pub chunk_size: usize,
+    /**
+     * Call FlushPrinter after every chunk, so latency sensitive printers (e.g. POS
+     * receipts) start printing right away (default false)
+     */
+    pub flush_each_chunk: bool,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            chunk_size: 64 * 1024,
+            flush_each_chunk: false,
+        }
+    }
+}
+
+/**
+ * Job name used when PrinterJobOptions::name is not set, followed by the
+ * submission time in milliseconds since the epoch to tell jobs apart
+ */
+pub const DEFAULT_JOB_NAME_PREFIX: &str = "rust-printers job";
+
+/**
+ * Return the default job name, like "rust-printers job 1760450000000"
+ */
+pub fn default_job_name() -> String {
+    let epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("{DEFAULT_JOB_NAME_PREFIX} {epoch}")
 }
 
 #[derive(Clone, Copy)]
 pub struct PrinterJobOptions<'a> {
+    /**
+     * Visual name/title of the job. On Windows control characters are removed
+     * and names longer than 255 UTF-16 units are truncated with an ellipsis
+     */
     pub name: Option<&'a str>,
     pub raw_properties: &'a [(&'a str, &'a str)],
+    /**
+     * Policy applied when copies, sides (duplex) or collate are not supported
+     */
+    pub on_unsupported: OnUnsupported,
+    /**
+     * Policy applied when the buffer or file is empty (default is Error)
+     */
+    pub on_empty: OnEmpty,
+    /**
+     * Typed document format, overrides the document-format raw property when set
+     */
+    pub document_format: Option<DocumentFormat>,
+    /**
+     * Fail before spooling if the printer is offline or paused (default false,
+     * since some workflows intentionally queue jobs to offline printers)
+     */
+    pub require_ready: bool,
+    /**
+     * How the buffer is written to the spooler (Windows only, CUPS spools a file)
+     */
+    pub stream: StreamOptions,
+    /**
+     * Write the job to this file instead of the printer port (Windows only, passed as
+     * DOC_INFO_1W.pOutputFile), used by print_to_pdf_file
+     */
+    pub output_file: Option<&'a str>,
 }
 
-impl PrinterJobOptions<'_> {
+impl<'a> PrinterJobOptions<'a> {
     pub fn none() -> Self {
         PrinterJobOptions {
             name: None,
             raw_properties: &[],
+            on_unsupported: OnUnsupported::default(),
+            on_empty: OnEmpty::default(),
+            document_format: None,
+            require_ready: false,
+            stream: StreamOptions::default(),
+            output_file: None,
+        }
+    }
+
+    /**
+     * Return the value of a raw property
+     */
+    pub fn get_raw_property(&self, key: &str) -> Option<&'a str> {
+        self.raw_properties
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    /**
+     * Return true if the content format should be detected, i.e. no format was given
+     * or it was explicitly set to Auto
+     */
+    pub fn is_auto_format(&self) -> bool {
+        match self.document_format {
+            Some(format) => format == DocumentFormat::Auto,
+            None => self.get_raw_property("document-format").is_none(),
         }
     }
+
+    /**
+     * Return true if the "hold" raw property is "true": the job is spooled paused, and
+     * only prints once released with Printer::resume_job (job-hold-until=indefinite on CUPS)
+     */
+    pub fn is_held(&self) -> bool {
+        self.get_raw_property("hold")
+            .is_some_and(|hold| hold.trim().eq_ignore_ascii_case("true"))
+    }
+
+    /**
+     * Return the image options matching these job options: same name and output file,
+     * the "copies" raw property as page count and the "form" raw property
+     */
+    pub(crate) fn image_options(&self) -> PrintImageOptions<'a> {
+        PrintImageOptions {
+            name: self.name,
+            page_count: self
+                .get_raw_property("copies")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(1),
+            form: self.get_raw_property("form"),
+            output_file: self.output_file,
+            ..PrintImageOptions::none()
+        }
+    }
+
+    /**
+     * Return true if copies, sides or collate were requested
+     */
+    pub fn has_device_options(&self) -> bool {
+        ["copies", "sides", "collate"]
+            .iter()
+            .any(|key| self.get_raw_property(key).is_some())
+    }
+}
+
+/**
+ * Job related limits of a printer
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrinterJobCapabilities {
+    /**
+     * Maximum number of copies, None when the printer does not report it
+     */
+    pub max_copies: Option<u32>,
+    /**
+     * Printer can print on both sides
+     */
+    pub duplex: bool,
+    /**
+     * Printer can collate copies
+     */
+    pub collate: bool,
+}
+
+/**
+ * Copies, sides and collate after validation against the printer capabilities
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatedJobOptions {
+    pub copies: u32,
+    pub duplex: bool,
+    pub collate: bool,
+    /**
+     * Names of the raw properties that were clamped
+     */
+    pub clamped: Vec<&'static str>,
+}
+
+impl PrinterJobCapabilities {
+    /**
+     * Validate copies, sides and collate together, clamping or failing per options.on_unsupported
+     */
+    pub fn validate(
+        &self,
+        options: &PrinterJobOptions,
+    ) -> Result<ValidatedJobOptions, &'static str> {
+        let mut validated = ValidatedJobOptions {
+            copies: options
+                .get_raw_property("copies")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(1),
+            duplex: options
+                .get_raw_property("sides")
+                .is_some_and(|v| v.starts_with("two-sided")),
+            collate: options
+                .get_raw_property("collate")
+                .is_some_and(|v| v == "true"),
+            clamped: vec![],
+        };
+
+        let clamp = options.on_unsupported == OnUnsupported::Clamp;
+
+        if let Some(max_copies) = self.max_copies
+            && validated.copies > max_copies
+        {
+            if !clamp {
+                return Err("Requested copies exceed the printer maximum");
+            }
+            validated.copies = max_copies;
+            validated.clamped.push("copies");
+        }
+
+        if validated.duplex && !self.duplex {
+            if !clamp {
+                return Err("Printer does not support duplex");
+            }
+            validated.duplex = false;
+            validated.clamped.push("sides");
+        }
+
+        if validated.collate && !self.collate {
+            if !clamp {
+                return Err("Printer does not support collate");
+            }
+            validated.collate = false;
+            validated.clamped.push("collate");
+        }
+
+        Ok(validated)
+    }
+}
+
+/**
+ * A job accepted by the spooler, with what is needed to track it
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSubmission {
+    /**
+     * Job ID
+     */
+    pub job_id: u64,
+    /**
+     * Copies requested from the printer, lower than the "copies" raw property when
+     * it was clamped to the driver maximum (0 when nothing was spooled)
+     */
+    pub copies: u32,
+    /**
+     * System name of the printer the job was sent to
+     */
+    pub printer_name: String,
+    /**
+     * Date when the job was submitted
+     */
+    pub submitted_at: SystemTime,
+    /**
+     * Copies written to the spooler, lower than copies when error cut the job short, so
+     * the copy that failed is at this zero-based index. None when the platform does not
+     * report it (CUPS spools every copy in a single request)
+     */
+    pub copies_spooled: Option<u32>,
+    /**
+     * Error that stopped the job after some copies were already spooled
+     */
+    pub error: Option<&'static str>,
+}
+
+impl JobSubmission {
+    /**
+     * Return true if every requested copy was spooled
+     */
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /**
+     * Return the job id qualified by its printer
+     */
+    pub fn global_id(&self) -> GlobalJobId {
+        GlobalJobId {
+            printer: self.printer_name.clone(),
+            id: self.job_id,
+        }
+    }
+}
+
+impl From<JobSubmission> for u64 {
+    fn from(submission: JobSubmission) -> Self {
+        submission.job_id
+    }
+}
+
+impl From<JobSubmission> for GlobalJobId {
+    fn from(submission: JobSubmission) -> Self {
+        GlobalJobId {
+            printer: submission.printer_name,
+            id: submission.job_id,
+        }
+    }
+}
+
+/**
+ * Job id together with the system name of its printer. Plain ids are only unique per
+ * printer, so this is the key to use when jobs of several printers are tracked together
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlobalJobId {
+    pub printer: String,
+    pub id: u64,
+}
+
+impl GlobalJobId {
+    /**
+     * Return the printer of the job
+     */
+    pub fn printer(&self) -> Option<Printer> {
+        crate::get_printer_by_exact_name(&self.printer)
+    }
+
+    /**
+     * Cancel the job on its printer
+     */
+    pub fn cancel(&self) -> Result<(), &'static str> {
+        self.printer().ok_or(PRINTER_NOT_FOUND)?.cancel_job(self.id)
+    }
+
+    /**
+     * Wait until the job finishes on its printer, see Printer::wait_for_job
+     */
+    pub fn wait(&self, timeout: Duration) -> Result<PrinterJobState, &'static str> {
+        self.printer()
+            .ok_or(PRINTER_NOT_FOUND)?
+            .wait_for_job(self.id, timeout)
+    }
+}
+
+impl Display for GlobalJobId {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "{}#{}", self.printer, self.id)
+    }
+}
+
+/**
+ * Result of a print job
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintResult {
+    /**
+     * Job ID
+     */
+    pub job_id: u64,
+    /**
+     * Number of pages sent to the printer. Data sent as is (RAW, text, files) counts one
+     * spooler page per copy on Windows, and is None on CUPS which spools it without a page count
+     */
+    pub pages_printed: Option<u32>,
+    /**
+     * Error that aborted the job after some pages were already sent. The failed page
+     * is the one after the pages sent, so its zero-based index is pages_printed
+     */
+    pub error: Option<&'static str>,
+}
+
+impl PrintResult {
+    /**
+     * Result of a job whose pages are not counted, see pages_printed
+     */
+    pub(crate) fn from_job_id(job_id: u64) -> Self {
+        PrintResult {
+            job_id,
+            pages_printed: None,
+            error: None,
+        }
+    }
+
+    /**
+     * Return true if every requested page was sent
+     */
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/**
+ * Return the range of a listing of `len` jobs that holds the page starting at `offset`
+ * with at most `limit` jobs, empty when the offset is past the end
+ */
+pub(crate) fn page_range(len: usize, offset: u32, limit: u32) -> Range<usize> {
+    let start = (offset as usize).min(len);
+    let end = start.saturating_add(limit as usize).min(len);
+    start..end
+}
+
+/**
+ * Return the jobs created at or after `since`, in listing order
+ */
+pub(crate) fn jobs_since(jobs: &[PrinterJob], since: SystemTime) -> Vec<PrinterJob> {
+    jobs.iter()
+        .filter(|j| j.created_at >= since)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_range_of_a_large_queue() {
+        let queue: Vec<u64> = (1..=50_000).collect();
+        let page = |offset, limit| queue[page_range(queue.len(), offset, limit)].to_vec();
+
+        assert_eq!(page(0, 3), vec![1, 2, 3]);
+        assert_eq!(page(49_998, 10), vec![49_999, 50_000]);
+        assert_eq!(page(10_000, 100).len(), 100);
+        assert_eq!(page(10_000, 100)[0], 10_001);
+        assert!(page(50_000, 10).is_empty());
+        assert!(page(u32::MAX, u32::MAX).is_empty());
+        assert!(page(5, 0).is_empty());
+        assert_eq!(page(0, u32::MAX).len(), queue.len());
+    }
+
+    fn job(id: u64, created_at: SystemTime) -> PrinterJob {
+        PrinterJob {
+            id,
+            name: format!("job {id}"),
+            state: PrinterJobState::PENDING,
+            media_type: "RAW".to_string(),
+            data_type: DataType::Raw,
+            created_at,
+            processed_at: None,
+            completed_at: None,
+            printer_name: "printer".to_string(),
+            user: "user".to_string(),
+            priority: 1,
+            size: 0,
+            next_job_id: None,
+        }
+    }
+
+    /**
+     * Job struct whose strings point into a listing buffer, like JOB_INFO_2W
+     */
+    struct BufferJob<'a> {
+        buffer: &'a [u8],
+        fields: [Range<usize>; 4],
+    }
+
+    impl PlatformPrinterJobGetters for BufferJob<'_> {
+        fn get_id(&self) -> u64 {
+            42
+        }
+        fn get_name(&self) -> String {
+            String::from_utf8_lossy(&self.buffer[self.fields[0].clone()]).into_owned()
+        }
+        fn get_state(&self) -> u64 {
+            0
+        }
+        fn get_printer(&self) -> String {
+            String::from_utf8_lossy(&self.buffer[self.fields[1].clone()]).into_owned()
+        }
+        fn get_user(&self) -> String {
+            String::from_utf8_lossy(&self.buffer[self.fields[2].clone()]).into_owned()
+        }
+        fn get_priority(&self) -> u32 {
+            1
+        }
+        fn get_size(&self) -> u64 {
+            0
+        }
+        fn get_media_type(&self) -> String {
+            String::from_utf8_lossy(&self.buffer[self.fields[3].clone()]).into_owned()
+        }
+        fn get_created_at(&self) -> SystemTime {
+            UNIX_EPOCH
+        }
+        fn get_processed_at(&self) -> Option<SystemTime> {
+            None
+        }
+        fn get_completed_at(&self) -> Option<SystemTime> {
+            None
+        }
+    }
+
+    #[test]
+    fn cloned_jobs_own_their_data() {
+        let buffer = b"report.pdfOfficealiceRAW".to_vec();
+        let jobs = vec![PrinterJob::from_platform_printer_job_getters(&BufferJob {
+            buffer: &buffer,
+            fields: [0..10, 10..16, 16..21, 21..24],
+        })];
+        // The listing buffer is freed right after the conversion
+        drop(buffer);
+
+        let cloned = jobs.clone();
+        let expected = format!("{:?}", jobs);
+        drop(jobs);
+
+        let job = &cloned[0];
+        assert_eq!(job.name, "report.pdf");
+        assert_eq!(job.printer_name, "Office");
+        assert_eq!(job.user, "alice");
+        assert_eq!(job.media_type, "RAW");
+        assert_eq!(job.data_type, DataType::Raw);
+        assert_eq!(format!("{:?}", cloned), expected);
+    }
+
+    #[test]
+    fn jobs_since_keeps_jobs_at_and_after_the_cutoff() {
+        let cutoff = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let jobs = [
+            job(1, cutoff - Duration::from_secs(3600)),
+            job(2, cutoff + Duration::from_millis(1)),
+            job(3, cutoff),
+            job(4, cutoff - Duration::from_millis(1)),
+            job(5, cutoff + Duration::from_secs(60)),
+        ];
+
+        let ids = |since| -> Vec<u64> { jobs_since(&jobs, since).iter().map(|j| j.id).collect() };
+        assert_eq!(ids(cutoff), vec![2, 3, 5]);
+        assert_eq!(ids(UNIX_EPOCH), vec![1, 2, 3, 4, 5]);
+        assert!(ids(cutoff + Duration::from_secs(61)).is_empty());
+        assert!(jobs_since(&[], cutoff).is_empty());
+    }
+
+    #[test]
+    fn job_state_predicates() {
+        let states = [
+            (PrinterJobState::PENDING, "Pending", false, false),
+            (PrinterJobState::PAUSED, "Paused", false, false),
+            (PrinterJobState::SPOOLING, "Spooling", false, false),
+            (PrinterJobState::PROCESSING, "Processing", false, false),
+            (PrinterJobState::CANCELLED, "Cancelled", true, true),
+            (PrinterJobState::COMPLETED, "Completed", true, false),
+            (PrinterJobState::UNKNOWN, "Unknown", false, false),
+        ];
+
+        for (state, text, terminal, error) in states {
+            assert_eq!(state.to_string(), text);
+            assert_eq!(state.is_terminal(), terminal, "{state}");
+            assert_eq!(state.is_error(), error, "{state}");
+        }
+    }
+
+    #[test]
+    fn detect_reads_magic_bytes() {
+        let detected = |buffer: &[u8]| DocumentFormat::detect(buffer);
+
+        assert_eq!(detected(b"%PDF-1.7\n%\xe2\xe3"), Some(DocumentFormat::Pdf));
+        assert_eq!(
+            detected(b"%!PS-Adobe-3.0\n"),
+            Some(DocumentFormat::PostScript)
+        );
+        assert_eq!(
+            detected(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some(DocumentFormat::Png)
+        );
+        assert_eq!(
+            detected(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some(DocumentFormat::Jpeg)
+        );
+        assert_eq!(detected(b"^XA^FDlabel^FS^XZ"), None);
+        assert_eq!(detected(b"\x1b%-12345X@PJL"), None);
+    }
+
+    #[test]
+    fn detect_ignores_short_buffers() {
+        for buffer in [
+            &b""[..],
+            b"%",
+            b"%PD",
+            b"%!P",
+            b"\x89PNG\r\n",
+            &[0xFF, 0xD8],
+        ] {
+            assert_eq!(DocumentFormat::detect(buffer), None, "{buffer:?}");
+        }
+    }
+
+    #[test]
+    fn auto_resolves_to_the_detected_mime_type() {
+        let mime = |buffer: &[u8]| DocumentFormat::Auto.resolve(buffer).mime_type();
+
+        assert_eq!(mime(b"%PDF-1.4"), "application/pdf");
+        assert_eq!(mime(b"\x89PNG\r\n\x1a\n"), "image/png");
+        assert_eq!(mime(&[0xFF, 0xD8, 0xFF, 0xDB]), "image/jpeg");
+        assert_eq!(mime(b"plain bytes"), "application/octet-stream");
+        assert_eq!(mime(b""), "application/octet-stream");
+        assert_eq!(
+            DocumentFormat::Raw.resolve(b"%PDF-1.4"),
+            DocumentFormat::Raw
+        );
+    }
 }
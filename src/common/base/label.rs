@@ -0,0 +1,27 @@
+use image::DynamicImage;
+
+/**
+ * What is printed on a label
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelContent {
+    /**
+     * Image scaled to fit the label
+     */
+    Image(DynamicImage),
+    /**
+     * Printer commands (ZPL, EPL, ESC/POS...) sent as is, the label size must also
+     * be set by the commands since Windows RAW jobs ignore the paper settings
+     */
+    Raw(Vec<u8>),
+}
+
+/**
+ * A label of an exact size for thermal label printers
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelSpec {
+    pub width_mm: f64,
+    pub height_mm: f64,
+    pub content: LabelContent,
+}
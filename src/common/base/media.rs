@@ -0,0 +1,297 @@
+const MM_PER_CM: f64 = 10.0;
+const MM_PER_INCH: f64 = 25.4;
+// Spooler form names are limited to CCHFORMNAME (32) characters including the terminator
+const MAX_FORM_NAME_LENGTH: usize = 31;
+
+/**
+ * Physical paper size in millimeters
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediaSize {
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+/**
+ * Paper form registered in the Windows spooler, like "A4" or "Letter"
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormInfo {
+    pub name: String,
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+/**
+ * Distance from each paper edge to the printable area, in millimeters
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FormMargins {
+    pub top_mm: f64,
+    pub right_mm: f64,
+    pub bottom_mm: f64,
+    pub left_mm: f64,
+}
+
+/**
+ * Definition of a custom form to register in the Windows spooler
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormSpec<'a> {
+    /**
+     * Form name, at most 31 characters
+     */
+    pub name: &'a str,
+    pub width_mm: f64,
+    pub height_mm: f64,
+    pub margins: FormMargins,
+}
+
+impl FormSpec<'_> {
+    /**
+     * Check the name length and that the margins leave a printable area inside the paper
+     */
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Form name cannot be empty");
+        }
+        if name.encode_utf16().count() > MAX_FORM_NAME_LENGTH {
+            return Err("Form name is too long");
+        }
+
+        let size_valid = |value: f64| value.is_finite() && value > 0.0;
+        if !size_valid(self.width_mm) || !size_valid(self.height_mm) {
+            return Err("Invalid form size");
+        }
+
+        let margins = [
+            self.margins.top_mm,
+            self.margins.right_mm,
+            self.margins.bottom_mm,
+            self.margins.left_mm,
+        ];
+        if margins.iter().any(|m| !m.is_finite() || *m < 0.0)
+            || self.margins.left_mm + self.margins.right_mm >= self.width_mm
+            || self.margins.top_mm + self.margins.bottom_mm >= self.height_mm
+        {
+            return Err("Invalid form margins");
+        }
+
+        Ok(())
+    }
+}
+
+impl MediaSize {
+    /**
+     * Return (width, height) in tenths of a millimeter, as used by DEVMODEW dmPaperWidth/dmPaperLength.
+     * Fails instead of wrapping when a side is longer than 3276.7mm (i16::MAX tenths)
+     */
+    pub fn to_tenths_mm(&self) -> Result<(i16, i16), &'static str> {
+        let to_tenths = |mm: f64| -> Result<i16, &'static str> {
+            let tenths = (mm * 10.0).round();
+            if !tenths.is_finite() || tenths < 0.0 {
+                return Err("Invalid paper size");
+            }
+            if tenths > i16::MAX as f64 {
+                return Err("Paper size is too large, sides are limited to 3276.7mm");
+            }
+            Ok(tenths as i16)
+        };
+        Ok((to_tenths(self.width_mm)?, to_tenths(self.height_mm)?))
+    }
+
+    /**
+     * Return the CUPS custom media name, like Custom.210x297mm
+     */
+    pub fn to_cups_media(&self) -> String {
+        format!(
+            "Custom.{}x{}mm",
+            format_mm(self.width_mm),
+            format_mm(self.height_mm)
+        )
+    }
+}
+
+/**
+ * Parse a media size like "210x297mm", "21x29.7cm", "8.5x11in", "210x297" (millimeters)
+ * or a common name (a3, a4, a5, letter, legal). Sizes embedded in PWG names such as
+ * "iso_a4_210x297mm" are also accepted
+ */
+pub fn parse_media_size(value: &str) -> Result<MediaSize, &'static str> {
+    let text = value.trim().to_ascii_lowercase();
+    if text.is_empty() {
+        return Err("Empty media size");
+    }
+
+    let size = [("mm", 1.0), ("cm", MM_PER_CM), ("in", MM_PER_INCH)]
+        .iter()
+        .find_map(|(suffix, factor)| {
+            let (w, h) = parse_xy_numbers(text.strip_suffix(suffix)?)?;
+            Some((w * factor, h * factor))
+        })
+        .or_else(|| parse_xy_numbers(text.as_str()))
+        .or_else(|| parse_named_media_mm(text.as_str()));
+
+    match size {
+        Some((width_mm, height_mm)) => Ok(MediaSize {
+            width_mm,
+            height_mm,
+        }),
+        None => Err("Invalid media size"),
+    }
+}
+
+fn parse_xy_numbers(text: &str) -> Option<(f64, f64)> {
+    // Sizes embedded in names never start with a sign, "-210x297" is not a size
+    if text.starts_with('-') {
+        return None;
+    }
+
+    let (left, right) = text.rsplit_once('x')?;
+    let w = parse_trailing_f64(left)?;
+    let h = parse_f64(right.trim_end())?;
+    if w.is_finite() && w > 0.0 && h.is_finite() && h > 0.0 {
+        Some((w, h))
+    } else {
+        None
+    }
+}
+
+fn parse_trailing_f64(text: &str) -> Option<f64> {
+    let end = text
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .count();
+    if end == 0 {
+        return None;
+    }
+
+    // The dot of names like "Custom.100x150" is not a decimal point
+    let start = text.len().checked_sub(end)?;
+    parse_f64(text[start..].trim_start_matches('.'))
+}
+
+fn parse_f64(text: &str) -> Option<f64> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    text.parse::<f64>().ok()
+}
+
+fn parse_named_media_mm(text: &str) -> Option<(f64, f64)> {
+    let normalized: String = text
+        .chars()
+        .filter(|c| *c != '-' && *c != '_' && !c.is_ascii_whitespace())
+        .collect();
+
+    match normalized.as_str() {
+        "a3" => Some((297.0, 420.0)),
+        "a4" => Some((210.0, 297.0)),
+        "a5" => Some((148.0, 210.0)),
+        "letter" => Some((215.9, 279.4)),
+        "legal" => Some((215.9, 355.6)),
+        _ => None,
+    }
+}
+
+fn format_mm(value: f64) -> String {
+    let formatted = format!("{value:.2}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(value: &str) -> (f64, f64) {
+        let size = parse_media_size(value).unwrap_or_else(|err| panic!("{value}: {err}"));
+        (size.width_mm, size.height_mm)
+    }
+
+    fn assert_size(value: &str, (width, height): (f64, f64)) {
+        let (w, h) = size(value);
+        assert!(
+            (w - width).abs() < 1e-9 && (h - height).abs() < 1e-9,
+            "{value}: {w}x{h}"
+        );
+    }
+
+    #[test]
+    fn parse_media_size_units() {
+        assert_size("210x297mm", (210.0, 297.0));
+        assert_size("21x29.7cm", (210.0, 297.0));
+        assert_size("8.5x11in", (215.9, 279.4));
+        assert_size("210x297", (210.0, 297.0));
+        assert_size("100.5x50", (100.5, 50.0));
+    }
+
+    #[test]
+    fn parse_media_size_case_and_whitespace() {
+        assert_size("  210X297MM ", (210.0, 297.0));
+        assert_size("8.5x11 In", (215.9, 279.4));
+        assert_size("\tA4\n", (210.0, 297.0));
+        assert_size("Letter", (215.9, 279.4));
+    }
+
+    #[test]
+    fn parse_media_size_names() {
+        assert_size("iso_a4_210x297mm", (210.0, 297.0));
+        assert_size("na_letter_8.5x11in", (215.9, 279.4));
+        assert_size("na_index-4x6_4x6in", (101.6, 152.4));
+        assert_size("Custom.100x150mm", (100.0, 150.0));
+        assert_size("a3", (297.0, 420.0));
+        assert_size("A-5", (148.0, 210.0));
+        assert_size("legal", (215.9, 355.6));
+    }
+
+    #[test]
+    fn parse_media_size_rejects_invalid_input() {
+        for value in [
+            "",
+            "   ",
+            "x",
+            "210x",
+            "x297",
+            "210x297km",
+            "210xx297",
+            "1.2.3x4",
+            "ax4",
+            "b4",
+            "0x297",
+            "210x0",
+            "0x0mm",
+            "-210x297mm",
+            "210x-297",
+            "-8.5x11in",
+        ] {
+            assert!(parse_media_size(value).is_err(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn to_tenths_mm_rounds_and_guards_i16() {
+        let media = |width_mm: f64, height_mm: f64| MediaSize {
+            width_mm,
+            height_mm,
+        };
+
+        assert_eq!(media(210.0, 297.0).to_tenths_mm(), Ok((2100, 2970)));
+        assert_eq!(media(215.9, 279.44).to_tenths_mm(), Ok((2159, 2794)));
+        assert_eq!(
+            media(3276.7, 3276.74).to_tenths_mm(),
+            Ok((i16::MAX, i16::MAX))
+        );
+
+        assert!(media(3276.8, 297.0).to_tenths_mm().is_err());
+        assert!(media(210.0, 10_000.0).to_tenths_mm().is_err());
+        assert!(media(-1.0, 297.0).to_tenths_mm().is_err());
+        assert!(media(f64::NAN, 297.0).to_tenths_mm().is_err());
+        assert!(media(210.0, f64::INFINITY).to_tenths_mm().is_err());
+    }
+}
@@ -0,0 +1,34 @@
+/**
+ * Right granted or denied on a printer queue
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrinterRight {
+    /**
+     * Submit jobs and manage its own jobs
+     */
+    Print,
+    /**
+     * Change the printer settings, share, pause or delete it
+     */
+    ManagePrinter,
+    /**
+     * Pause, restart, reorder and cancel the jobs of every user
+     */
+    ManageDocuments,
+}
+
+/**
+ * Rights of a user or group on a printer, read from its security descriptor
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessControlEntry {
+    /**
+     * Account name as DOMAIN\name, like "BUILTIN\Administrators"
+     */
+    pub principal: String,
+    /**
+     * true when the rights are granted, false when they are explicitly denied
+     */
+    pub allowed: bool,
+    pub rights: Vec<PrinterRight>,
+}
@@ -1,11 +1,37 @@
-use std::fmt::{Debug, Error, Formatter};
-use image::DynamicImage;
-use super::job::{PrinterJob, PrinterJobOptions};
+use super::content::PrintContent;
+use super::image::{PrintImageOptions, ScaleMode, TileOptions, test_page_image, tile_image};
+use super::job::{
+    DocumentFormat, JobInfoLevel, JobSortOrder, JobSubmission, PrintResult, PrinterJob,
+    PrinterJobCapabilities, PrinterJobOptions, ValidatedJobOptions, jobs_since,
+};
+use super::label::{LabelContent, LabelSpec};
+use super::media::{FormInfo, FormSpec, MediaSize};
+use super::permissions::AccessControlEntry;
+use super::session::PrintSession;
+use crate::common::traits::platform::DeviceCaps;
 use crate::common::{
     base::job::PrinterJobState,
     traits::platform::{PlatformActions, PlatformPrinterGetters},
 };
-use crate::common::traits::platform::DeviceCaps;
+use image::DynamicImage;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::fs;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/**
+ * Error returned when no printer has the given name, distinct from access or spooler failures
+ */
+pub const PRINTER_NOT_FOUND: &str = "Printer not found";
+const TEST_PAGE_DPI: f64 = 100.0;
+const PING_PORTS: [u16; 3] = [9100, 631, 515];
+const MM_PER_INCH: f64 = 25.4;
+const CROP_MARK_MM: f64 = 5.0;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PrinterState {
@@ -16,6 +42,92 @@ pub enum PrinterState {
     UNKNOWN,
 }
 
+/**
+ * Connection type of a printer, parsed from its port name (Windows) or device uri (CUPS)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortKind {
+    Usb,
+    TcpIp { host: String },
+    Wsd,
+    Serial,
+    File,
+    Other,
+}
+
+impl PortKind {
+    /**
+     * Classify a port name like USB001, IP_192.168.1.5, WSD-..., COM3, FILE:
+     * or a device uri like usb://..., socket://host:9100, ipp://host/...
+     */
+    pub fn from_port_name(port_name: &str) -> PortKind {
+        let port = port_name.trim();
+        let lower = port.to_ascii_lowercase();
+
+        if let Some((scheme, rest)) = lower.split_once("://") {
+            let host = || {
+                let authority = rest.split(['/', '?']).next().unwrap_or_default();
+                let authority = authority.rsplit('@').next().unwrap_or_default();
+                match authority.strip_prefix('[') {
+                    Some(ipv6) => ipv6.split(']').next().unwrap_or_default().to_string(),
+                    None => authority.split(':').next().unwrap_or_default().to_string(),
+                }
+            };
+            return match scheme {
+                "usb" => PortKind::Usb,
+                "socket" | "ipp" | "ipps" | "lpd" | "http" | "https" => {
+                    PortKind::TcpIp { host: host() }
+                }
+                "serial" => PortKind::Serial,
+                "file" => PortKind::File,
+                _ => PortKind::Other,
+            };
+        }
+
+        if lower.starts_with("usb") {
+            PortKind::Usb
+        } else if lower.starts_with("wsd") {
+            PortKind::Wsd
+        } else if let Some(host) = lower.strip_prefix("ip_") {
+            PortKind::TcpIp {
+                host: port[port.len() - host.len()..].to_string(),
+            }
+        } else if lower.starts_with("serial:")
+            || (lower.starts_with("com") && lower[3..].trim_end_matches(':').parse::<u32>().is_ok())
+        {
+            PortKind::Serial
+        } else if lower == "portprompt:" || lower.starts_with("file:") {
+            PortKind::File
+        } else if is_ip_port(port) {
+            // Standard TCP/IP ports are often named after the address, like 192.168.1.5_1
+            PortKind::TcpIp {
+                host: port.split('_').next().unwrap_or_default().to_string(),
+            }
+        } else {
+            PortKind::Other
+        }
+    }
+}
+
+fn is_ip_port(port: &str) -> bool {
+    let address = port.split('_').next().unwrap_or_default();
+    address.split('.').count() == 4 && address.split('.').all(|part| part.parse::<u8>().is_ok())
+}
+
+/**
+ * A printer port as reported by the spooler (Windows only)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortInfo {
+    pub name: String,
+    pub monitor: String,
+    pub description: String,
+    /**
+     * PORT_TYPE_* flags (write, read, redirected, net attached)
+     */
+    pub port_type: u32,
+}
+
 /**
  * Printer is a struct to representation the system printer
  */
@@ -70,6 +182,11 @@ pub struct Printer {
      */
     pub is_shared: bool,
 
+    /**
+     * Share name of the printer, None when it is not shared
+     */
+    pub share_name: Option<String>,
+
     /**
      * The state of the printer
      */
@@ -93,6 +210,7 @@ impl Debug for Printer {
                 \r  uri: {:?},
                 \r  port_name: {:?},
                 \r  is_shared: {:?},
+                \r  share_name: {:?},
                 \r  location: {:?},
                 \r  driver_name: {:?}
                 \r  processor: {:?}
@@ -106,6 +224,7 @@ impl Debug for Printer {
             self.uri,
             self.port_name,
             self.is_shared,
+            self.share_name,
             self.location,
             self.driver_name,
             self.processor,
@@ -127,6 +246,7 @@ impl Clone for Printer {
             system_name: self.system_name.clone(),
             driver_name: self.driver_name.clone(),
             is_shared: self.is_shared,
+            share_name: self.share_name.clone(),
             data_type: self.data_type.clone(),
             description: self.description.clone(),
             processor: self.processor.clone(),
@@ -144,6 +264,10 @@ impl Printer {
             state_reasons.push("none".to_string());
         }
 
+        let is_shared = platform_printer.get_is_shared();
+        let share_name = Some(platform_printer.get_share_name())
+            .filter(|share_name| is_shared && !share_name.is_empty());
+
         Printer {
             name: platform_printer.get_name(),
             system_name: platform_printer.get_system_name(),
@@ -151,7 +275,8 @@ impl Printer {
             location: platform_printer.get_location(),
             uri: platform_printer.get_uri(),
             port_name: platform_printer.get_port_name(),
-            is_shared: platform_printer.get_is_shared(),
+            is_shared,
+            share_name,
             data_type: platform_printer.get_data_type(),
             processor: platform_printer.get_processor(),
             description: platform_printer.get_description(),
@@ -163,42 +288,499 @@ impl Printer {
         }
     }
 
+    /**
+     * Return true if `name` matches the printer name or system name,
+     * ignoring case and surrounding whitespace
+     */
+    pub fn matches_name(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.name.trim().to_lowercase() == name || self.system_name.trim().to_lowercase() == name
+    }
+
+    /**
+     * Return true if the printer is a connection to a queue of a print server (\\host\printer)
+     */
+    fn is_connection(&self) -> bool {
+        self.system_name.starts_with("\\\\")
+    }
+
+    /**
+     * Lowercased names the queue is reached by: its system name, the \\host\share of a
+     * connection, and for a local queue the remote queue its port prints to and the paths a
+     * connection from `computer_name` back to it would use
+     */
+    fn queue_keys(&self, computer_name: Option<&str>) -> Vec<String> {
+        let system_name = self.system_name.trim();
+        let mut keys = vec![system_name.to_lowercase()];
+
+        if self.is_connection() {
+            let host = system_name[2..].split('\\').next().unwrap_or_default();
+            if let Some(share_name) = &self.share_name {
+                keys.push(format!("\\\\{host}\\{share_name}").to_lowercase());
+            }
+            return keys;
+        }
+
+        let port_name = self.port_name.trim();
+        if port_name.starts_with("\\\\") {
+            keys.push(port_name.to_lowercase());
+        }
+        if let Some(host) = computer_name {
+            keys.push(format!("\\\\{host}\\{system_name}").to_lowercase());
+            if let Some(share_name) = &self.share_name {
+                keys.push(format!("\\\\{host}\\{share_name}").to_lowercase());
+            }
+        }
+        keys
+    }
+
+    /**
+     * Return the \\host\share path of a shared printer, using the local machine name.
+     * None when the printer is not shared or the name cannot be resolved (always on CUPS)
+     */
+    pub fn unc_path(&self) -> Option<String> {
+        let share_name = self.share_name.as_ref()?;
+        let host = crate::Platform::get_computer_name()?;
+        Some(format!("\\\\{host}\\{share_name}"))
+    }
+
+    /**
+     * Return the connection type parsed from port_name, which stays available as is
+     */
+    pub fn port_kind(&self) -> PortKind {
+        PortKind::from_port_name(&self.port_name)
+    }
+
+    /**
+     * Check that a network printer answers by opening a TCP connection to its raw (9100),
+     * IPP (631) or LPD (515) port, which the spooler status cannot tell when the device
+     * is powered off. `timeout` applies to each connection attempt. Fails for ports that
+     * are not TCP/IP
+     */
+    pub fn ping(&self, timeout: Duration) -> Result<bool, &'static str> {
+        let PortKind::TcpIp { host } = self.port_kind() else {
+            return Err("Reachability can only be checked for TCP/IP ports");
+        };
+
+        Ok(PING_PORTS.iter().any(|port| {
+            (host.as_str(), *port)
+                .to_socket_addrs()
+                .map(|mut addrs| {
+                    addrs.any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok())
+                })
+                .unwrap_or(false)
+        }))
+    }
+
+    /**
+     * Return the resolution, page size and margins from the driver default settings.
+     * On Windows they come from a device context of the printer, which does not
+     * depend on the port the printer is attached to
+     */
     pub fn get_printer_caps(&self) -> DeviceCaps {
         crate::Platform::get_printer_caps(self.system_name.as_str())
     }
-    
+
+    /**
+     * Return the copies, duplex and collate support of the printer
+     */
+    pub fn get_job_capabilities(&self) -> PrinterJobCapabilities {
+        crate::Platform::get_job_capabilities(self.system_name.as_str())
+    }
+
+    /**
+     * Validate copies, sides and collate of the options, returning the effective
+     * values and which of them were clamped
+     */
+    pub fn validate_job_options(
+        &self,
+        options: &PrinterJobOptions,
+    ) -> Result<ValidatedJobOptions, &'static str> {
+        self.get_job_capabilities().validate(options)
+    }
+
     /**
      * Print bytes
      */
-    pub fn print(&self, buffer: &[u8], options: PrinterJobOptions) -> Result<u64, &'static str> {
-        crate::Platform::print(self.system_name.as_str(), buffer, options)
+    pub fn print(
+        &self,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+    ) -> Result<JobSubmission, &'static str> {
+        self.submit_buffer(buffer, options, |buffer, options| {
+            crate::Platform::print(self.system_name.as_str(), buffer, options)
+        })
+    }
+
+    /**
+     * Open a session keeping the printer open to print several jobs, see PrintSession
+     */
+    pub fn open_session(&self) -> Result<PrintSession, &'static str> {
+        PrintSession::open(self.clone())
+    }
+
+    /**
+     * Run the checks shared by print and PrintSession::print, then spool with `send`
+     */
+    pub(crate) fn submit_buffer(
+        &self,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+        send: impl FnOnce(&[u8], PrinterJobOptions) -> Result<PrintResult, &'static str>,
+    ) -> Result<JobSubmission, &'static str> {
+        if buffer.is_empty() {
+            return self.empty_submission(&options);
+        }
+        self.check_ready(&options)?;
+        let copies = self.effective_copies(&options)?;
+        send(buffer, options).map(|result| self.job_submission(result, copies))
     }
 
     /**
      * Print file
+     * On Windows a PDF file detected with the Auto format is an error since there is no PDF
+     * renderer, set options.document_format to send it as RAW to a printer reading PDF
      */
     pub fn print_file(
         &self,
         file_path: &str,
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str> {
+    ) -> Result<JobSubmission, &'static str> {
+        if fs::metadata(file_path).is_ok_and(|metadata| metadata.len() == 0) {
+            return self.empty_submission(&options);
+        }
+        self.check_ready(&options)?;
+        let copies = self.effective_copies(&options)?;
         crate::Platform::print_file(self.system_name.as_str(), file_path, options)
+            .map(|result| self.job_submission(result, copies))
+    }
+
+    /**
+     * Print the data of a reader, like an open File, a pipe or a socket, streamed to the
+     * spooler without a temporary file (on Windows several copies are read into memory)
+     */
+    pub fn print_reader<R: Read>(
+        &self,
+        mut reader: R,
+        options: PrinterJobOptions,
+    ) -> Result<JobSubmission, &'static str> {
+        // Peek one byte so an empty reader follows options.on_empty like an empty buffer
+        let mut head = Vec::with_capacity(1);
+        (&mut reader)
+            .take(1)
+            .read_to_end(&mut head)
+            .map_err(|_| "Failed to read the document")?;
+        if head.is_empty() {
+            return self.empty_submission(&options);
+        }
+        self.check_ready(&options)?;
+        let copies = self.effective_copies(&options)?;
+        crate::Platform::print_reader(
+            self.system_name.as_str(),
+            &mut head.as_slice().chain(reader),
+            options,
+        )
+        .map(|result| self.job_submission(result, copies))
+    }
+
+    fn empty_submission(&self, options: &PrinterJobOptions) -> Result<JobSubmission, &'static str> {
+        options
+            .on_empty
+            .empty_result()
+            .map(|result| self.job_submission(result, 0))
+    }
+
+    /**
+     * Copies the platform will request, after clamping to the driver maximum
+     * or failing per options.on_unsupported
+     */
+    fn effective_copies(&self, options: &PrinterJobOptions) -> Result<u32, &'static str> {
+        if options.has_device_options() {
+            Ok(self.validate_job_options(options)?.copies)
+        } else {
+            Ok(1)
+        }
     }
 
+    /**
+     * Wrap the result of a buffer or file job, whose spooler pages are its copies
+     */
+    fn job_submission(&self, result: PrintResult, copies: u32) -> JobSubmission {
+        JobSubmission {
+            job_id: result.job_id,
+            copies,
+            printer_name: self.system_name.clone(),
+            submitted_at: SystemTime::now(),
+            copies_spooled: result.pages_printed,
+            error: result.error,
+        }
+    }
+
+    /**
+     * With require_ready, read the current printer state and refuse offline or paused printers,
+     * or PRINTER_NOT_FOUND when the printer was removed
+     */
+    fn check_ready(&self, options: &PrinterJobOptions) -> Result<(), &'static str> {
+        if !options.require_ready {
+            return Ok(());
+        }
+
+        let printer = crate::Platform::get_printer_by_name(self.system_name.as_str())
+            .ok_or(PRINTER_NOT_FOUND)?;
+
+        match printer.state {
+            PrinterState::OFFLINE => Err("Printer is offline"),
+            PrinterState::PAUSED => Err("Printer is paused"),
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Print an image, sending it once per page, scaled to fit the printable area.
+     * If the job fails after some pages were already sent, the result reports how
+     * many were printed and the error
+     */
     pub fn print_image(
         &self,
         image: DynamicImage,
         print_name: Option<&str>,
         page_count: u32,
         print_width: Option<f64>,
-        print_height: Option<f64>,) -> Result<u64, &'static str> {
-        crate::Platform::print_image(self.system_name.as_str(),
-                                     image,
-                                     print_name,
-                                     page_count,
-                                     print_width,
-                                     print_height)
+        print_height: Option<f64>,
+    ) -> Result<PrintResult, &'static str> {
+        self.print_image_with_options(
+            image,
+            PrintImageOptions {
+                name: print_name,
+                page_count,
+                print_width,
+                print_height,
+                ..PrintImageOptions::none()
+            },
+        )
+    }
+
+    /**
+     * Print an image with explicit options, e.g. ScaleMode::Center to keep the native size
+     */
+    pub fn print_image_with_options(
+        &self,
+        image: DynamicImage,
+        options: PrintImageOptions,
+    ) -> Result<PrintResult, &'static str> {
+        if options.page_count == 0 {
+            return Err("Page count must be at least 1");
+        }
+        // Cropped here so every platform prints the same part of the image
+        let image = match options.src_rect {
+            Some(rect) if !rect.is_within(image.width(), image.height()) => {
+                return Err("Source rect is outside the image");
+            }
+            Some(rect) => image.crop_imm(rect.x, rect.y, rect.width, rect.height),
+            None => image,
+        };
+        crate::Platform::print_image(
+            self.system_name.as_str(),
+            image,
+            PrintImageOptions {
+                src_rect: None,
+                ..options
+            },
+        )
+    }
+
+    /**
+     * Print a test page with a border, a centered crosshair and millimeter rulers through
+     * print_image, sized to the printable area so offsets and scaling can be checked.
+     * The spooler test pages are not used since they cannot report the job id
+     */
+    pub fn print_test_page(&self) -> Result<u64, &'static str> {
+        let caps = self.get_printer_caps();
+        // Rendered at TEST_PAGE_DPI and scaled back by Fit, so a millimeter stays a millimeter
+        let (width, height) = if caps.dpi_x > 0
+            && caps.dpi_y > 0
+            && caps.print_table_width > 0
+            && caps.print_table_height > 0
+        {
+            (
+                (caps.print_table_width as f64 * TEST_PAGE_DPI / caps.dpi_x as f64).round() as u32,
+                (caps.print_table_height as f64 * TEST_PAGE_DPI / caps.dpi_y as f64).round() as u32,
+            )
+        } else {
+            // A4 when the driver reports no printable area
+            (827, 1169)
+        };
+
+        self.print_image_with_options(
+            test_page_image(width.max(1), height.max(1), TEST_PAGE_DPI / MM_PER_INCH),
+            PrintImageOptions {
+                name: Some("Test Page"),
+                scale_mode: ScaleMode::Fit,
+                ..PrintImageOptions::none()
+            },
+        )
+        .map(|result| result.job_id)
+    }
+
+    /**
+     * Print an image larger than the page, like a poster, split across as many sheets as
+     * needed at its physical size (source_dpi). Each tile is its own job, printing stops at
+     * the first tile that fails and the results of the tiles sent so far are returned
+     */
+    pub fn print_image_tiled(
+        &self,
+        image: DynamicImage,
+        options: TileOptions,
+    ) -> Result<Vec<PrintResult>, &'static str> {
+        if !options.source_dpi.is_finite() || options.source_dpi <= 0.0 {
+            return Err("Source DPI must be greater than zero");
+        }
+
+        let caps = self.get_printer_caps();
+        if caps.dpi_x <= 0
+            || caps.dpi_y <= 0
+            || caps.print_table_width <= 0
+            || caps.print_table_height <= 0
+        {
+            return Err("Printer did not report its printable area");
+        }
+
+        // Tiles cover the printable area at the source resolution and are scaled back by Fit
+        let tile_width =
+            (caps.print_table_width as f64 * options.source_dpi / caps.dpi_x as f64).floor() as u32;
+        let tile_height = (caps.print_table_height as f64 * options.source_dpi / caps.dpi_y as f64)
+            .floor() as u32;
+        let px_per_mm = options.source_dpi / MM_PER_INCH;
+        let overlap = (options.overlap_mm * px_per_mm).round();
+        if !overlap.is_finite() || overlap < 0.0 || overlap >= tile_width.min(tile_height) as f64 {
+            return Err("Overlap must be smaller than the printable area");
+        }
+        let overlap = overlap as u32;
+        let mark_length = options
+            .crop_marks
+            .then(|| (CROP_MARK_MM * px_per_mm).round().max(1.0) as u32);
+
+        let mut results = vec![];
+        for tile in tile_image(
+            image,
+            (tile_width.max(1), tile_height.max(1)),
+            (overlap, overlap),
+            mark_length,
+        ) {
+            let result = self.print_image_with_options(
+                tile,
+                PrintImageOptions {
+                    name: options.name,
+                    scale_mode: ScaleMode::Fit,
+                    ..PrintImageOptions::none()
+                },
+            )?;
+
+            let complete = result.is_complete();
+            results.push(result);
+            if !complete {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /**
+     * Print a label of an exact size. Images are scaled to fit a page of the label size,
+     * raw content is sent without processing with the size as media on CUPS
+     */
+    pub fn print_label(&self, label: LabelSpec) -> Result<PrintResult, &'static str> {
+        let size_valid = |value: f64| value.is_finite() && value > 0.0;
+        if !size_valid(label.width_mm) || !size_valid(label.height_mm) {
+            return Err("Invalid label size");
+        }
+
+        match label.content {
+            LabelContent::Image(image) => self.print_image_with_options(
+                image,
+                PrintImageOptions {
+                    print_width: Some(label.width_mm),
+                    print_height: Some(label.height_mm),
+                    scale_mode: ScaleMode::Fit,
+                    ..PrintImageOptions::none()
+                },
+            ),
+            LabelContent::Raw(buffer) => {
+                let media = MediaSize {
+                    width_mm: label.width_mm,
+                    height_mm: label.height_mm,
+                }
+                .to_cups_media();
+                let submission = self.print(
+                    &buffer,
+                    PrinterJobOptions {
+                        raw_properties: &[("media", media.as_str())],
+                        document_format: Some(DocumentFormat::Raw),
+                        ..PrinterJobOptions::none()
+                    },
+                )?;
+                Ok(PrintResult {
+                    job_id: submission.job_id,
+                    pages_printed: submission.copies_spooled,
+                    error: submission.error,
+                })
+            }
+        }
+    }
+
+    /**
+     * Print any kind of content, dispatched to print, print_file or print_image_with_options.
+     * Images take their options from the job options (see PrinterJobOptions::image_options).
+     * Returns one result per job, Images stop at the first image that fails
+     */
+    pub fn submit(
+        &self,
+        content: PrintContent,
+        options: PrinterJobOptions,
+    ) -> Result<Vec<PrintResult>, &'static str> {
+        // Raw, text and file jobs only count the spooler pages written for their copies
+        let submitted = |submission: JobSubmission| PrintResult {
+            job_id: submission.job_id,
+            pages_printed: submission.copies_spooled,
+            error: submission.error,
+        };
+
+        let images = match content {
+            PrintContent::Raw(buffer) => return Ok(vec![submitted(self.print(&buffer, options)?)]),
+            PrintContent::File(path) => {
+                let path = path.to_str().ok_or("Invalid file path")?;
+                return Ok(vec![submitted(self.print_file(path, options)?)]);
+            }
+            PrintContent::Text(text) => {
+                let options = PrinterJobOptions {
+                    document_format: options.document_format.or(Some(DocumentFormat::Text)),
+                    ..options
+                };
+                return Ok(vec![submitted(self.print(text.as_bytes(), options)?)]);
+            }
+            PrintContent::Image(image) => vec![image],
+            PrintContent::Images(images) => images,
+        };
+
+        // Image jobs are rendered by the library, the hold raw property cannot reach them
+        if options.is_held() {
+            return Err("Held jobs are not supported for images");
+        }
+
+        let mut results = Vec::with_capacity(images.len());
+        for image in images {
+            let result = self.print_image_with_options(image, options.image_options())?;
+            let complete = result.is_complete();
+            results.push(result);
+            if !complete {
+                break;
+            }
+        }
+
+        Ok(results)
     }
+
     /**
      * Return active jobs
      */
@@ -213,6 +795,198 @@ impl Printer {
         crate::Platform::get_printer_jobs(self.system_name.as_str(), false)
     }
 
+    /**
+     * Return active or historic jobs with the given level of detail, Basic being cheaper
+     * for pollers that only track ids and states (see JobInfoLevel for the fields it fills)
+     */
+    pub fn get_jobs_level(&self, active_only: bool, level: JobInfoLevel) -> Vec<PrinterJob> {
+        crate::Platform::get_printer_jobs_level(self.system_name.as_str(), active_only, level)
+    }
+
+    /**
+     * Return active or historic jobs in the given order
+     */
+    pub fn get_jobs_sorted(&self, active_only: bool, order: JobSortOrder) -> Vec<PrinterJob> {
+        let mut jobs = crate::Platform::get_printer_jobs(self.system_name.as_str(), active_only);
+        order.sort(&mut jobs);
+        jobs
+    }
+
+    /**
+     * Return a page of jobs, skipping the first `offset` jobs and returning at most `limit`.
+     * Windows only reads the page from the spooler, CUPS fetches the whole job history first
+     */
+    pub fn get_jobs_paged(&self, offset: u32, limit: u32) -> Vec<PrinterJob> {
+        crate::Platform::get_printer_jobs_paged(self.system_name.as_str(), offset, limit)
+    }
+
+    /**
+     * Return jobs submitted by a specific user
+     */
+    pub fn get_jobs_by_user(&self, user: &str, active_only: bool) -> Vec<PrinterJob> {
+        crate::Platform::get_printer_jobs(self.system_name.as_str(), active_only)
+            .into_iter()
+            .filter(|j| j.user == user)
+            .collect()
+    }
+
+    /**
+     * Return jobs created at or after `since`, so a poller only gets the jobs it has not seen
+     */
+    pub fn get_jobs_since(&self, since: SystemTime, active_only: bool) -> Vec<PrinterJob> {
+        let jobs = crate::Platform::get_printer_jobs(self.system_name.as_str(), active_only);
+        jobs_since(&jobs, since)
+    }
+
+    /**
+     * Return a job followed by the jobs chained to it through next_job_id
+     */
+    pub fn get_job_chain(&self, job_id: u64) -> Vec<PrinterJob> {
+        let mut jobs = self.get_job_history();
+        let mut chain: Vec<PrinterJob> = Vec::new();
+        let mut next = Some(job_id);
+
+        while let Some(id) = next {
+            // Stop on missing links and on cycles
+            let Some(position) = jobs.iter().position(|j| j.id == id) else {
+                break;
+            };
+            let job = jobs.swap_remove(position);
+            next = job.next_job_id;
+            chain.push(job);
+        }
+
+        chain
+    }
+
+    /**
+     * Return the paper forms known by the printer (Windows only, empty on unix)
+     */
+    pub fn get_forms(&self) -> Vec<FormInfo> {
+        crate::Platform::get_forms(self.system_name.as_str())
+    }
+
+    /**
+     * Register a custom form that jobs can then select by name (Windows only, requires administrator privileges)
+     */
+    pub fn add_form(&self, form: &FormSpec) -> Result<(), &'static str> {
+        form.validate()?;
+        crate::Platform::add_form(self.system_name.as_str(), form)
+    }
+
+    /**
+     * Remove a custom form by name (Windows only, requires administrator privileges)
+     */
+    pub fn delete_form(&self, form_name: &str) -> Result<(), &'static str> {
+        crate::Platform::delete_form(self.system_name.as_str(), form_name.trim())
+    }
+
+    /**
+     * Return who can print on, manage or manage the documents of the printer (Windows only)
+     */
+    pub fn get_permissions(&self) -> Result<Vec<AccessControlEntry>, &'static str> {
+        crate::Platform::get_printer_permissions(self.system_name.as_str())
+    }
+
+    /**
+     * Share the printer on the network or stop sharing it, which requires administrator
+     * rights. On Windows the share name defaults to the current one, then the printer name.
+     * CUPS shares queues under their own name and ignores `share_name`
+     */
+    pub fn set_shared(&self, shared: bool, share_name: Option<&str>) -> Result<(), &'static str> {
+        if share_name.is_some_and(|name| name.trim().is_empty()) {
+            return Err("Share name cannot be empty");
+        }
+        crate::Platform::set_printer_shared(self.system_name.as_str(), shared, share_name)
+    }
+
+    /**
+     * Return the directory where the printer jobs are spooled
+     */
+    pub fn get_spool_directory(&self) -> PathBuf {
+        crate::Platform::get_spool_directory(self.system_name.as_str())
+    }
+
+    /**
+     * Return the total size in bytes of the jobs currently spooled
+     */
+    pub fn get_spool_usage(&self) -> u64 {
+        self.get_active_jobs().iter().map(|j| j.size).sum()
+    }
+
+    /**
+     * Return true if the job is still known by the printer queue
+     */
+    pub fn job_exists(&self, job_id: u64) -> bool {
+        crate::Platform::job_exists(&self.system_name, job_id)
+    }
+
+    /**
+     * Poll a job until it reaches a terminal state or the timeout elapses.
+     * A job that existed and then left the queue is reported as completed,
+     * while a job that never appeared is an error
+     */
+    pub fn wait_for_job(
+        &self,
+        job_id: u64,
+        timeout: Duration,
+    ) -> Result<PrinterJobState, &'static str> {
+        self.track_job(job_id, timeout, |_| {})
+    }
+
+    /**
+     * Like wait_for_job, but call `callback` with every state change up to the terminal one.
+     * The job is polled every 500ms, so transitions faster than that may be coalesced
+     */
+    pub fn track_job<F>(
+        &self,
+        job_id: u64,
+        timeout: Duration,
+        mut callback: F,
+    ) -> Result<PrinterJobState, &'static str>
+    where
+        F: FnMut(PrinterJobState),
+    {
+        let started_at = Instant::now();
+        let mut last_state: Option<PrinterJobState> = None;
+        let mut emit = |state: PrinterJobState| {
+            if last_state.as_ref() != Some(&state) {
+                last_state = Some(state.clone());
+                callback(state);
+            }
+        };
+        let mut seen = false;
+
+        loop {
+            let job = self.get_job_history().into_iter().find(|j| j.id == job_id);
+
+            match job {
+                Some(job) => {
+                    seen = true;
+                    emit(job.state.clone());
+                    if job.state.is_terminal() {
+                        return Ok(job.state);
+                    }
+                }
+                // The listing may race with the job leaving the queue, so confirm directly
+                None if !self.job_exists(job_id) => {
+                    return if seen {
+                        emit(PrinterJobState::COMPLETED);
+                        Ok(PrinterJobState::COMPLETED)
+                    } else {
+                        Err("Job not found in the printer queue")
+                    };
+                }
+                None => seen = true,
+            }
+
+            if started_at.elapsed() >= timeout {
+                return Err("Timed out waiting for the job");
+            }
+            thread::sleep(JOB_POLL_INTERVAL);
+        }
+    }
+
     /**
      * Pause an printer job
      */
@@ -240,10 +1014,261 @@ impl Printer {
     pub fn cancel_job(&self, job_id: u64) -> Result<(), &'static str> {
         crate::Platform::set_job_state(&self.system_name, job_id, PrinterJobState::CANCELLED)
     }
+
+    /**
+     * Cancel the active jobs whose name is `name` as stored by the spooler (see the Windows
+     * limits of PrinterJobOptions::name): every match when `all` is true, otherwise only the
+     * most recently created one. Return how many jobs were cancelled: a job that fails to
+     * cancel does not stop the others, the error is only returned when no match was cancelled
+     */
+    pub fn cancel_job_by_name(&self, name: &str, all: bool) -> Result<usize, &'static str> {
+        let mut matches: Vec<PrinterJob> =
+            crate::Platform::get_printer_jobs(self.system_name.as_str(), true)
+                .into_iter()
+                .filter(|j| j.name == name)
+                .collect();
+
+        if !all {
+            matches.sort_by_key(|j| std::cmp::Reverse(j.created_at));
+            matches.truncate(1);
+        }
+
+        let mut cancelled = 0;
+        let mut error = None;
+        for job in &matches {
+            match self.cancel_job(job.id) {
+                Ok(()) => cancelled += 1,
+                Err(err) => error = error.or(Some(err)),
+            }
+        }
+
+        match error {
+            Some(err) if cancelled == 0 => Err(err),
+            _ => Ok(cancelled),
+        }
+    }
+
+    /**
+     * Change the queue priority of an printer job
+     */
+    pub fn set_job_priority(&self, job_id: u64, priority: u32) -> Result<(), &'static str> {
+        crate::Platform::set_job_priority(&self.system_name, job_id, priority)
+    }
+}
+
+/**
+ * Keep a single entry per queue, in listing order. Entries are the same queue when any of
+ * their queue keys match (see Printer::queue_keys), and a local queue replaces a connection
+ * to it whatever order they are listed in
+ */
+pub(crate) fn deduplicate_printers(
+    printers: Vec<Printer>,
+    computer_name: Option<&str>,
+) -> Vec<Printer> {
+    let mut kept: Vec<(Vec<String>, Printer)> = Vec::new();
+
+    for printer in printers {
+        let keys = printer.queue_keys(computer_name);
+        match kept
+            .iter_mut()
+            .find(|(kept_keys, _)| kept_keys.iter().any(|key| keys.contains(key)))
+        {
+            Some((kept_keys, kept_printer)) => {
+                if kept_printer.is_connection() && !printer.is_connection() {
+                    *kept_printer = printer;
+                }
+                kept_keys.extend(keys);
+            }
+            None => kept.push((keys, printer)),
+        }
+    }
+
+    kept.into_iter().map(|(_, printer)| printer).collect()
+}
+
+/**
+ * Sort printers for a picker: the printer whose system name is `default_name` first,
+ * then the others by name, ignoring case
+ */
+pub(crate) fn sort_default_first(printers: &mut [Printer], default_name: Option<&str>) {
+    printers.sort_by_cached_key(|p| {
+        (
+            default_name != Some(p.system_name.as_str()),
+            p.name.to_lowercase(),
+        )
+    });
 }
 
 impl PrinterState {
-    pub(crate) fn from_platform_state(platform_state: u64, state_reasons: &str) -> Self {
+    /**
+     * Convert a raw platform printer state (PRINTER_INFO_2W.Status on Windows,
+     * printer-state on CUPS) and its comma separated reasons into a PrinterState
+     */
+    pub fn from_platform_state(platform_state: u64, state_reasons: &str) -> Self {
         crate::Platform::parse_printer_state(platform_state, state_reasons)
     }
+
+    /**
+     * Return true if the printer cannot accept work
+     */
+    pub fn is_error(&self) -> bool {
+        *self == PrinterState::OFFLINE
+    }
+}
+
+impl Display for PrinterState {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let text = match self {
+            PrinterState::READY => "Ready",
+            PrinterState::OFFLINE => "Offline",
+            PrinterState::PAUSED => "Paused",
+            PrinterState::PRINTING => "Printing",
+            PrinterState::UNKNOWN => "Unknown",
+        };
+        write!(fmt, "{text}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printer(system_name: &str, port_name: &str, share_name: Option<&str>) -> Printer {
+        Printer {
+            name: system_name.to_string(),
+            system_name: system_name.to_string(),
+            driver_name: String::new(),
+            uri: String::new(),
+            port_name: port_name.to_string(),
+            processor: String::new(),
+            data_type: "RAW".to_string(),
+            description: String::new(),
+            location: String::new(),
+            is_shared: share_name.is_some(),
+            share_name: share_name.map(str::to_string),
+            state: PrinterState::READY,
+            state_reasons: vec!["none".to_string()],
+        }
+    }
+
+    fn names(printers: &[Printer]) -> Vec<&str> {
+        printers.iter().map(|p| p.system_name.as_str()).collect()
+    }
+
+    #[test]
+    fn names_match_ignoring_case_and_whitespace() {
+        let mut laserjet = printer("HP LaserJet 4", "USB001", None);
+        laserjet.name = "HP LaserJet".to_string();
+
+        for name in [
+            "HP LaserJet",
+            "hp laserjet",
+            "  hp LaserJet ",
+            "\tHP LASERJET\n",
+            "hp laserjet 4",
+        ] {
+            assert!(laserjet.matches_name(name), "{name:?}");
+        }
+        for name in ["HP LaserJet 5", "hp  laserjet", "LaserJet", ""] {
+            assert!(!laserjet.matches_name(name), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn sort_lists_the_default_printer_first() {
+        let listing = || {
+            ["zebra", "\\\\server\\Office", "alpha", "Brother", "Canon"]
+                .map(|name| printer(name, "", None))
+                .to_vec()
+        };
+        let sorted = |default_name| {
+            let mut printers = listing();
+            sort_default_first(&mut printers, default_name);
+            names(&printers)
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sorted(Some("Canon")),
+            ["Canon", "\\\\server\\Office", "alpha", "Brother", "zebra"]
+        );
+        assert_eq!(
+            sorted(Some("zebra")),
+            ["zebra", "\\\\server\\Office", "alpha", "Brother", "Canon"]
+        );
+        assert_eq!(
+            sorted(None),
+            ["\\\\server\\Office", "alpha", "Brother", "Canon", "zebra"]
+        );
+        assert_eq!(sorted(Some("missing")), sorted(None));
+    }
+
+    #[test]
+    fn deduplicate_prefers_the_local_queue() {
+        // A connection to a local shared queue, listed before and after it
+        let local = printer("Office", "USB001", Some("OfficeShare"));
+        let connection = printer("\\\\DESK\\Office", "", Some("OfficeShare"));
+
+        for listing in [
+            vec![connection.clone(), local.clone()],
+            vec![local.clone(), connection.clone()],
+        ] {
+            let printers = deduplicate_printers(listing, Some("desk"));
+            assert_eq!(names(&printers), vec!["Office"]);
+        }
+    }
+
+    #[test]
+    fn deduplicate_matches_local_ports_to_remote_queues() {
+        let printers = deduplicate_printers(
+            vec![
+                printer("\\\\server\\Label", "", Some("LABEL")),
+                printer("Labels", "\\\\SERVER\\label", None),
+                printer("Zebra", "USB001", None),
+                printer("\\\\server\\Other", "", Some("Other")),
+            ],
+            Some("DESK"),
+        );
+
+        assert_eq!(
+            names(&printers),
+            vec!["Labels", "Zebra", "\\\\server\\Other"]
+        );
+    }
+
+    #[test]
+    fn deduplicate_keeps_distinct_queues() {
+        let listing = vec![
+            printer("Office", "USB001", None),
+            printer("\\\\server\\Office", "", Some("Office")),
+            printer("\\\\other\\Office", "", Some("Office")),
+            printer("office", "USB002", None),
+        ];
+
+        // Without the computer name a connection cannot be told to point at a local queue,
+        // the local entries differing only by case are still one queue
+        let printers = deduplicate_printers(listing, None);
+        assert_eq!(
+            names(&printers),
+            vec!["Office", "\\\\server\\Office", "\\\\other\\Office"]
+        );
+    }
+
+    #[test]
+    fn printer_state_predicates() {
+        let states = [
+            (PrinterState::READY, "Ready", false),
+            (PrinterState::OFFLINE, "Offline", true),
+            (PrinterState::PAUSED, "Paused", false),
+            (PrinterState::PRINTING, "Printing", false),
+            (PrinterState::UNKNOWN, "Unknown", false),
+        ];
+
+        for (state, text, error) in states {
+            assert_eq!(state.to_string(), text);
+            assert_eq!(state.is_error(), error, "{state}");
+        }
+    }
 }
@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::common::base::printer::Printer;
+use crate::common::traits::platform::PlatformActions;
+
+/**
+ * Shared list of the system printers that is enumerated once and refreshed on demand
+ * or periodically with watch, for apps that keep the list around
+ */
+#[derive(Debug)]
+pub struct Printers {
+    printers: RwLock<Vec<Printer>>,
+    default_name: RwLock<Option<String>>,
+}
+
+impl Printers {
+    /**
+     * Create the registry with the printers currently available
+     */
+    // Not Default: creating the registry enumerates the printers, and default() returns the default printer
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let registry = Printers {
+            printers: RwLock::new(Vec::new()),
+            default_name: RwLock::new(None),
+        };
+        registry.refresh();
+        registry
+    }
+
+    /**
+     * Enumerate the printers again and replace the cached list
+     */
+    pub fn refresh(&self) {
+        let printers = crate::Platform::get_printers();
+        *self.printers.write().unwrap_or_else(|e| e.into_inner()) = printers;
+        self.refresh_default();
+    }
+
+    /**
+     * Look up the default printer again, it is a user setting the spooler does not notify
+     */
+    fn refresh_default(&self) {
+        let default_name = crate::Platform::get_default_printer().map(|p| p.system_name);
+        *self.default_name.write().unwrap_or_else(|e| e.into_inner()) = default_name;
+    }
+
+    /**
+     * Return a copy of every cached printer
+     */
+    pub fn all(&self) -> Vec<Printer> {
+        self.printers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /**
+     * Return a cached printer by name, matched like get_printer_by_name
+     */
+    pub fn get(&self, name: &str) -> Option<Printer> {
+        let printers = self.printers.read().unwrap_or_else(|e| e.into_inner());
+        printers
+            .iter()
+            .find(|p| p.system_name == name.trim())
+            .or_else(|| printers.iter().find(|p| p.matches_name(name)))
+            .cloned()
+    }
+
+    /**
+     * Return the cached default printer
+     */
+    pub fn default(&self) -> Option<Printer> {
+        let default_name = self
+            .default_name
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()?;
+        self.printers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|p| p.system_name == default_name)
+            .cloned()
+    }
+
+    /**
+     * Check for changes every `interval` on a background thread, until the returned watch
+     * or the registry itself is dropped. On Windows the printers are only enumerated again
+     * when the spooler change cookie moved (see get_printers_cached), elsewhere they are
+     * polled. The default printer is looked up on every check
+     */
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> PrintersWatch {
+        let registry: Weak<Printers> = Arc::downgrade(self);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+
+        let handle = thread::spawn(move || {
+            // Unknown until the first check, changes made before the watch started are picked up
+            let mut last_cookie = None;
+            loop {
+                thread::park_timeout(interval);
+                if thread_stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+
+                let cookie = crate::Platform::printers_change_cookie();
+                if cookie.is_some() && cookie == last_cookie {
+                    registry.refresh_default();
+                } else {
+                    registry.refresh();
+                    last_cookie = cookie;
+                }
+            }
+        });
+
+        PrintersWatch {
+            stopped,
+            handle: Some(handle),
+        }
+    }
+}
+
+/**
+ * Background refresh started by Printers::watch, stopped when dropped
+ */
+#[derive(Debug)]
+pub struct PrintersWatch {
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for PrintersWatch {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
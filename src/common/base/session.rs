@@ -0,0 +1,49 @@
+use crate::common::base::job::{JobSubmission, PrinterJobOptions};
+use crate::common::base::printer::Printer;
+use crate::common::traits::platform::PlatformActions;
+
+/**
+ * A printer kept open to print several jobs without reopening it for each one.
+ * On Windows the spooler handle stays open until the session is dropped, every job
+ * is a complete document so nothing is left in progress. On CUPS each job is still
+ * sent on its own
+ */
+pub struct PrintSession {
+    printer: Printer,
+    session: <crate::Platform as PlatformActions>::Session,
+}
+
+impl PrintSession {
+    pub(crate) fn open(printer: Printer) -> Result<Self, &'static str> {
+        Ok(PrintSession {
+            session: crate::Platform::open_session(printer.system_name.as_str())?,
+            printer,
+        })
+    }
+
+    /**
+     * Return the printer of the session
+     */
+    pub fn printer(&self) -> &Printer {
+        &self.printer
+    }
+
+    /**
+     * Print bytes as a new job, with the same checks as Printer::print
+     */
+    pub fn print(
+        &self,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+    ) -> Result<JobSubmission, &'static str> {
+        self.printer
+            .submit_buffer(buffer, options, |buffer, options| {
+                crate::Platform::print_in_session(
+                    &self.session,
+                    self.printer.system_name.as_str(),
+                    buffer,
+                    options,
+                )
+            })
+    }
+}
@@ -0,0 +1,171 @@
+use super::job::PrinterJob;
+use super::printer::Printer;
+use crate::common::traits::platform::PlatformActions;
+use std::collections::HashMap;
+
+/**
+ * Change between two successive job listings
+ */
+#[derive(Debug, Clone, PartialEq)]
+// Boxing Updated would break matching on it, events are short lived anyway
+#[allow(clippy::large_enum_variant)]
+pub enum JobEvent {
+    Added(PrinterJob),
+    /**
+     * Previous and current version of a job that changed (state, size, etc.)
+     */
+    Updated(PrinterJob, PrinterJob),
+    Removed(PrinterJob),
+}
+
+/**
+ * Polls the jobs of a printer and reports what changed since the previous poll
+ */
+pub struct JobWatcher {
+    printer_name: String,
+    active_only: bool,
+    snapshot: HashMap<u64, PrinterJob>,
+}
+
+impl JobWatcher {
+    /**
+     * Watch active or historic jobs of a printer. The first poll reports every job as added
+     */
+    pub fn new(printer: &Printer, active_only: bool) -> Self {
+        JobWatcher {
+            printer_name: printer.system_name.clone(),
+            active_only,
+            snapshot: HashMap::new(),
+        }
+    }
+
+    /**
+     * Fetch the jobs again and return the events since the previous poll
+     */
+    pub fn poll_diff(&mut self) -> Vec<JobEvent> {
+        let jobs = crate::Platform::get_printer_jobs(&self.printer_name, self.active_only);
+        let events = diff_jobs(&self.snapshot, &jobs);
+        self.snapshot = jobs.into_iter().map(|j| (j.id, j)).collect();
+        events
+    }
+}
+
+/**
+ * Compare a previous snapshot keyed by job id with the current listing.
+ * Added and updated jobs follow the listing order, removed jobs come last
+ */
+pub fn diff_jobs(previous: &HashMap<u64, PrinterJob>, current: &[PrinterJob]) -> Vec<JobEvent> {
+    let mut events: Vec<JobEvent> = current
+        .iter()
+        .filter_map(|job| match previous.get(&job.id) {
+            None => Some(JobEvent::Added(job.clone())),
+            Some(old) if old != job => Some(JobEvent::Updated(old.clone(), job.clone())),
+            Some(_) => None,
+        })
+        .collect();
+
+    let mut removed: Vec<&PrinterJob> = previous
+        .values()
+        .filter(|old| !current.iter().any(|j| j.id == old.id))
+        .collect();
+    removed.sort_by_key(|j| j.id);
+    events.extend(removed.into_iter().cloned().map(JobEvent::Removed));
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::base::job::{DataType, PrinterJobState};
+    use std::time::UNIX_EPOCH;
+
+    fn job(id: u64, state: PrinterJobState) -> PrinterJob {
+        PrinterJob {
+            id,
+            name: format!("job {id}"),
+            state,
+            media_type: "RAW".to_string(),
+            data_type: DataType::Raw,
+            created_at: UNIX_EPOCH,
+            processed_at: None,
+            completed_at: None,
+            printer_name: "printer".to_string(),
+            user: "user".to_string(),
+            priority: 1,
+            size: 0,
+            next_job_id: None,
+        }
+    }
+
+    fn snapshot(jobs: &[PrinterJob]) -> HashMap<u64, PrinterJob> {
+        jobs.iter().map(|j| (j.id, j.clone())).collect()
+    }
+
+    #[test]
+    fn unchanged_snapshot_has_no_events() {
+        let jobs = [
+            job(1, PrinterJobState::PENDING),
+            job(2, PrinterJobState::PROCESSING),
+        ];
+        assert!(diff_jobs(&snapshot(&jobs), &jobs).is_empty());
+        assert!(diff_jobs(&HashMap::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn new_jobs_are_added_in_listing_order() {
+        let before = [job(1, PrinterJobState::PENDING)];
+        let after = [
+            job(3, PrinterJobState::PENDING),
+            before[0].clone(),
+            job(2, PrinterJobState::PENDING),
+        ];
+
+        assert_eq!(
+            diff_jobs(&snapshot(&before), &after),
+            vec![
+                JobEvent::Added(after[0].clone()),
+                JobEvent::Added(after[2].clone())
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_jobs_are_updated_with_both_versions() {
+        let before = [
+            job(1, PrinterJobState::PENDING),
+            job(2, PrinterJobState::PENDING),
+        ];
+        let mut printing = before[1].clone();
+        printing.state = PrinterJobState::PROCESSING;
+        printing.size = 1024;
+        let after = [before[0].clone(), printing.clone()];
+
+        assert_eq!(
+            diff_jobs(&snapshot(&before), &after),
+            vec![JobEvent::Updated(before[1].clone(), printing)]
+        );
+    }
+
+    #[test]
+    fn missing_jobs_are_removed_last_by_id() {
+        let before = [
+            job(5, PrinterJobState::PENDING),
+            job(2, PrinterJobState::PENDING),
+            job(4, PrinterJobState::PENDING),
+        ];
+        let after = [
+            job(4, PrinterJobState::PENDING),
+            job(6, PrinterJobState::PENDING),
+        ];
+
+        assert_eq!(
+            diff_jobs(&snapshot(&before), &after),
+            vec![
+                JobEvent::Added(after[1].clone()),
+                JobEvent::Removed(before[1].clone()),
+                JobEvent::Removed(before[0].clone()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u16, month: u16) -> u16 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/**
+ * Convert the fields of a UTC date (like the Windows SYSTEMTIME of JOB_INFO_2W.Submitted)
+ * into a SystemTime. Months and days are 1-based, no local offset is applied
+ */
+pub fn system_time_from_utc(
+    year: u16,
+    month: u16,
+    day: u16,
+    hour: u16,
+    minute: u16,
+    second: u16,
+    milliseconds: u16,
+) -> SystemTime {
+    let mut total_days: u64 = day.saturating_sub(1) as u64;
+
+    for year in 1970..year {
+        total_days += if is_leap_year(year) { 366 } else { 365 };
+    }
+
+    // Add the full months before the current one
+    for month in 1..month.min(13) {
+        total_days += days_in_month(year, month) as u64;
+    }
+
+    let total_seconds = (total_days * 24 * 60 * 60)
+        + (hour as u64 * 60 * 60)
+        + (minute as u64 * 60)
+        + (second as u64);
+
+    UNIX_EPOCH + Duration::new(total_seconds, milliseconds as u32 * 1_000_000)
+}
+
+/**
+ * Convert seconds since the Unix epoch (like the CUPS time_t job times) into a SystemTime,
+ * None for zero or negative values, which CUPS uses for times not reached yet
+ */
+pub fn system_time_from_unix(seconds: impl Into<i64>) -> Option<SystemTime> {
+    let seconds = seconds.into();
+    if seconds > 0 {
+        Some(UNIX_EPOCH + Duration::from_secs(seconds as u64))
+    } else {
+        None
+    }
+}
+
+/**
+ * Return the seconds elapsed since the Unix epoch, 0 if the clock is set before it
+ */
+pub fn current_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_utc(year: u16, month: u16, day: u16, hour: u16, minute: u16, second: u16) -> u64 {
+        system_time_from_utc(year, month, day, hour, minute, second, 0)
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn epoch_start_is_unix_epoch() {
+        assert_eq!(system_time_from_utc(1970, 1, 1, 0, 0, 0, 0), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn leap_years_follow_the_gregorian_rules() {
+        // 2000 is leap (multiple of 400), 2100 is not (multiple of 100), 2024 is
+        assert_eq!(from_utc(2000, 2, 29, 0, 0, 0), 951_782_400);
+        assert_eq!(from_utc(2000, 3, 1, 0, 0, 0), 951_868_800);
+        assert_eq!(from_utc(2100, 2, 28, 0, 0, 0), 4_107_456_000);
+        assert_eq!(from_utc(2100, 3, 1, 0, 0, 0), 4_107_542_400);
+        assert_eq!(from_utc(2024, 2, 29, 12, 0, 0), 1_709_208_000);
+        assert_eq!(from_utc(2024, 3, 1, 0, 0, 0), 1_709_251_200);
+    }
+
+    #[test]
+    fn month_and_year_boundaries_are_one_second_apart() {
+        assert_eq!(from_utc(2023, 1, 31, 0, 0, 0), 1_675_123_200);
+        assert_eq!(from_utc(2023, 2, 1, 0, 0, 0), 1_675_209_600);
+        assert_eq!(from_utc(2023, 12, 31, 23, 59, 59), 1_704_067_199);
+        assert_eq!(from_utc(2024, 1, 1, 0, 0, 0), 1_704_067_200);
+    }
+
+    #[test]
+    fn job_submitted_systemtime_is_read_as_utc() {
+        // JOB_INFO Submitted of 2024-01-02 03:04:05.006 UTC
+        assert_eq!(
+            system_time_from_utc(2024, 1, 2, 3, 4, 5, 6),
+            UNIX_EPOCH + Duration::from_millis(1_704_164_645_006)
+        );
+    }
+
+    #[test]
+    fn unix_times_before_the_epoch_are_none() {
+        assert_eq!(system_time_from_unix(0), None);
+        assert_eq!(system_time_from_unix(-1), None);
+        assert_eq!(
+            system_time_from_unix(1_704_067_200),
+            Some(UNIX_EPOCH + Duration::from_secs(1_704_067_200))
+        );
+    }
+}
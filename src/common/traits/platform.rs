@@ -1,9 +1,15 @@
 use crate::common::base::{
-    job::{PrinterJobOptions, PrinterJobState},
-    printer::{Printer, PrinterState},
+    image::PrintImageOptions,
+    job::{JobInfoLevel, PrintResult, PrinterJobCapabilities, PrinterJobOptions, PrinterJobState},
+    media::{FormInfo, FormSpec},
+    permissions::AccessControlEntry,
+    printer::{PortInfo, Printer, PrinterState},
 };
-use std::time::SystemTime;
 use image::DynamicImage;
+use std::{io::Read, path::PathBuf, time::SystemTime};
+
+const MM_PER_INCH: f64 = 25.4;
+const POINTS_PER_INCH: f64 = 72.0;
 
 #[derive(Clone, Debug)]
 pub struct DeviceCaps {
@@ -19,6 +25,169 @@ pub struct DeviceCaps {
     pub margin_bottom: i32,
 }
 
+impl DeviceCaps {
+    /**
+     * Build caps from the raw driver values, deriving the right and bottom margins.
+     * Drivers with inconsistent values (printable area larger than the page or outside it)
+     * get the printable area clamped to the page and centered, so margins are never negative
+     */
+    pub fn from_driver_values(
+        dpi: (i32, i32),
+        page: (i32, i32),
+        printable: (i32, i32),
+        offset: (i32, i32),
+    ) -> Self {
+        let (margin_left, print_table_width, margin_right) =
+            normalize_margins(page.0, printable.0, offset.0);
+        let (margin_top, print_table_height, margin_bottom) =
+            normalize_margins(page.1, printable.1, offset.1);
+
+        DeviceCaps {
+            dpi_x: dpi.0,
+            dpi_y: dpi.1,
+            page_width: page.0,
+            page_height: page.1,
+            print_table_width,
+            print_table_height,
+            margin_top,
+            margin_left,
+            margin_right,
+            margin_bottom,
+        }
+    }
+
+    /**
+     * Return the printable area (x, y, width, height) in device units, relative to the
+     * top left corner of the physical page
+     */
+    pub fn printable_rect(&self) -> (i32, i32, i32, i32) {
+        (
+            self.margin_left,
+            self.margin_top,
+            self.print_table_width,
+            self.print_table_height,
+        )
+    }
+
+    /**
+     * Return the offset (x, y) of the printable area from the physical page corner.
+     * Printer device contexts start at the printable area, so coordinates measured
+     * from the paper edge must be translated by minus this offset
+     */
+    pub fn content_offset(&self) -> (i32, i32) {
+        (self.margin_left, self.margin_top)
+    }
+
+    /**
+     * Convert the sizes and margins to `unit` with the device resolution. Without a
+     * reported resolution only Pixels can be converted, other units are all zero
+     */
+    pub fn in_units(&self, unit: Unit) -> MeasuredCaps {
+        let per_inch = match unit {
+            Unit::Pixels => None,
+            Unit::Mm => Some(MM_PER_INCH),
+            Unit::Inch => Some(1.0),
+            Unit::Point => Some(POINTS_PER_INCH),
+        };
+        let convert = |value: i32, dpi: i32| match per_inch {
+            None => value as f64,
+            Some(_) if dpi <= 0 => 0.0,
+            Some(per_inch) => value as f64 / dpi as f64 * per_inch,
+        };
+        let x = |value: i32| convert(value, self.dpi_x);
+        let y = |value: i32| convert(value, self.dpi_y);
+
+        MeasuredCaps {
+            unit,
+            page_width: x(self.page_width),
+            page_height: y(self.page_height),
+            print_table_width: x(self.print_table_width),
+            print_table_height: y(self.print_table_height),
+            margin_top: y(self.margin_top),
+            margin_left: x(self.margin_left),
+            margin_right: x(self.margin_right),
+            margin_bottom: y(self.margin_bottom),
+        }
+    }
+}
+
+/**
+ * Unit of the values of MeasuredCaps
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    /**
+     * Device pixels, as reported in DeviceCaps
+     */
+    Pixels,
+    Mm,
+    Inch,
+    /**
+     * Typographic points, 72 per inch
+     */
+    Point,
+}
+
+/**
+ * DeviceCaps sizes and margins converted to a unit, see DeviceCaps::in_units
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeasuredCaps {
+    pub unit: Unit,
+    pub page_width: f64,
+    pub page_height: f64,
+    pub print_table_width: f64,
+    pub print_table_height: f64,
+    pub margin_top: f64,
+    pub margin_left: f64,
+    pub margin_right: f64,
+    pub margin_bottom: f64,
+}
+
+/**
+ * Return (leading margin, printable length, trailing margin) along one page axis
+ */
+fn normalize_margins(page: i32, printable: i32, offset: i32) -> (i32, i32, i32) {
+    if page <= 0 {
+        return (offset.max(0), printable.max(0), 0);
+    }
+
+    let printable = printable.clamp(0, page);
+    if offset >= 0 && offset.saturating_add(printable) <= page {
+        (offset, printable, page - printable - offset)
+    } else {
+        let margin = (page - printable) / 2;
+        (margin, printable, page - printable - margin)
+    }
+}
+
+/**
+ * Features implemented by the printing backend of the current platform
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeatureSet {
+    /**
+     * Sending bytes and files to a printer
+     */
+    pub printing: bool,
+    /**
+     * Rendering images with print_image
+     */
+    pub image_printing: bool,
+    /**
+     * Pausing, resuming, restarting and cancelling jobs
+     */
+    pub job_control: bool,
+    /**
+     * Changing job priorities
+     */
+    pub job_priority: bool,
+    /**
+     * Installing and removing printers
+     */
+    pub installation: bool,
+}
+
 pub trait PlatformPrinterGetters {
     fn get_name(&self) -> String;
     fn get_system_name(&self) -> String;
@@ -32,13 +201,24 @@ pub trait PlatformPrinterGetters {
     fn get_processor(&self) -> String;
     fn get_description(&self) -> String;
     fn get_data_type(&self) -> String;
+    fn get_share_name(&self) -> String;
 }
 
+/**
+ * Fields of a platform job struct. Every getter returns owned data copied out of the struct,
+ * which usually lives in an EnumJobsW or cupsGetJobs buffer freed right after the conversion
+ */
 pub trait PlatformPrinterJobGetters {
     fn get_id(&self) -> u64;
     fn get_name(&self) -> String;
     fn get_state(&self) -> u64;
     fn get_printer(&self) -> String;
+    fn get_user(&self) -> String;
+    fn get_priority(&self) -> u32;
+    fn get_size(&self) -> u64;
+    /**
+     * Spooled data format (Windows datatype or CUPS MIME type), not the paper type
+     */
     fn get_media_type(&self) -> String;
     fn get_created_at(&self) -> SystemTime;
     fn get_processed_at(&self) -> Option<SystemTime>;
@@ -46,33 +226,84 @@ pub trait PlatformPrinterJobGetters {
 }
 
 pub trait PlatformActions {
+    /**
+     * State kept open by a PrintSession between jobs
+     */
+    type Session;
+
+    fn supported_features() -> FeatureSet;
     fn get_printers() -> Vec<Printer>;
+    fn try_get_printers() -> Result<Vec<Printer>, &'static str>;
+    /**
+     * Return a value that changes whenever the list of printers changes, None when the
+     * platform does not report changes
+     */
+    fn printers_change_cookie() -> Option<u64>;
 
     fn get_printer_caps(printer_system_name: &str) -> DeviceCaps;
+    fn get_job_capabilities(printer_system_name: &str) -> PrinterJobCapabilities;
     fn print(
         printer_system_name: &str,
         buffer: &[u8],
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str>;
+    ) -> Result<PrintResult, &'static str>;
+    fn open_session(printer_system_name: &str) -> Result<Self::Session, &'static str>;
+    fn print_in_session(
+        session: &Self::Session,
+        printer_system_name: &str,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str>;
     fn print_file(
         printer_system_name: &str,
         file_path: &str,
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str>;
+    ) -> Result<PrintResult, &'static str>;
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str>;
     fn print_image(
         printer_system_name: &str,
         buffer: DynamicImage,
-        print_name: Option<&str>,
-        page_count: u32,
-        print_width: Option<f64>,
-        print_height: Option<f64>,
-    ) -> Result<u64, &'static str>;
+        options: PrintImageOptions,
+    ) -> Result<PrintResult, &'static str>;
     fn get_printer_jobs(
         printer_name: &str,
         active_only: bool,
     ) -> Vec<crate::common::base::job::PrinterJob>;
+    fn get_printer_jobs_level(
+        printer_name: &str,
+        active_only: bool,
+        level: JobInfoLevel,
+    ) -> Vec<crate::common::base::job::PrinterJob>;
+    fn get_printer_jobs_paged(
+        printer_name: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<crate::common::base::job::PrinterJob>;
+    fn get_spool_directory(printer_name: &str) -> PathBuf;
+    fn get_ports() -> Vec<PortInfo>;
+    fn get_forms(printer_name: &str) -> Vec<FormInfo>;
+    fn add_form(printer_name: &str, form: &FormSpec) -> Result<(), &'static str>;
+    fn delete_form(printer_name: &str, form_name: &str) -> Result<(), &'static str>;
+    fn get_printer_permissions(printer_name: &str)
+    -> Result<Vec<AccessControlEntry>, &'static str>;
+    fn get_print_processors() -> Vec<String>;
+    /**
+     * Name of the local machine that share paths start with, None where shares do not exist
+     */
+    fn get_computer_name() -> Option<String>;
+    fn rename_printer(printer_name: &str, new_name: &str) -> Result<(), &'static str>;
+    fn set_printer_shared(
+        printer_name: &str,
+        shared: bool,
+        share_name: Option<&str>,
+    ) -> Result<(), &'static str>;
     fn get_default_printer() -> Option<Printer>;
     fn get_printer_by_name(printer_name: &str) -> Option<Printer>;
+    fn get_printer_status(printer_name: &str) -> Option<(u64, Vec<String>)>;
     fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState;
     fn parse_printer_job_state(platform_state: u64) -> PrinterJobState;
     fn set_job_state(
@@ -80,4 +311,71 @@ pub trait PlatformActions {
         job_id: u64,
         state: PrinterJobState,
     ) -> Result<(), &'static str>;
+    fn job_exists(printer_name: &str, job_id: u64) -> bool;
+    fn set_job_priority(printer_name: &str, job_id: u64, priority: u32)
+    -> Result<(), &'static str>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn margins(caps: &DeviceCaps) -> (i32, i32, i32, i32) {
+        (
+            caps.margin_top,
+            caps.margin_right,
+            caps.margin_bottom,
+            caps.margin_left,
+        )
+    }
+
+    #[test]
+    fn consistent_driver_values_are_kept() {
+        let caps =
+            DeviceCaps::from_driver_values((600, 600), (4960, 7016), (4760, 6816), (100, 80));
+        assert_eq!(
+            (caps.print_table_width, caps.print_table_height),
+            (4760, 6816)
+        );
+        assert_eq!(margins(&caps), (80, 100, 120, 100));
+    }
+
+    #[test]
+    fn negative_margins_fall_back_to_centered() {
+        let caps =
+            DeviceCaps::from_driver_values((300, 300), (2480, 3508), (2400, 3400), (-50, -8));
+        assert_eq!(
+            (caps.print_table_width, caps.print_table_height),
+            (2400, 3400)
+        );
+        // Symmetric margins, the odd unit goes to the trailing side
+        assert_eq!(margins(&caps), (54, 40, 54, 40));
+
+        let caps = DeviceCaps::from_driver_values((300, 300), (2480, 3508), (-10, 3508), (-1, 0));
+        assert_eq!(caps.print_table_width, 0);
+        assert_eq!((caps.margin_left, caps.margin_right), (1240, 1240));
+    }
+
+    #[test]
+    fn printable_area_larger_than_page_is_clamped() {
+        let caps = DeviceCaps::from_driver_values((300, 300), (2480, 3508), (3000, 3509), (0, 0));
+        assert_eq!(
+            (caps.print_table_width, caps.print_table_height),
+            (2480, 3508)
+        );
+        assert_eq!(margins(&caps), (0, 0, 0, 0));
+
+        // The offset pushes the printable area past the page edge
+        let caps =
+            DeviceCaps::from_driver_values((300, 300), (2480, 3508), (2400, 3401), (100, i32::MAX));
+        assert_eq!((caps.margin_left, caps.margin_right), (40, 40));
+        assert_eq!((caps.margin_top, caps.margin_bottom), (53, 54));
+    }
+
+    #[test]
+    fn zero_page_keeps_non_negative_values() {
+        let caps = DeviceCaps::from_driver_values((0, 0), (0, -1), (-5, 100), (-3, 7));
+        assert_eq!((caps.print_table_width, caps.print_table_height), (0, 100));
+        assert_eq!(margins(&caps), (7, 0, 0, 0));
+    }
 }
@@ -0,0 +1,131 @@
+//! Encode text for printers that read RAW text in a fixed code page, like receipt and
+//! label printers, e.g. with `DocumentFormat::Raw`
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+mod tables;
+
+/**
+ * Byte written for characters the encoding cannot represent
+ */
+const REPLACEMENT: u8 = b'?';
+
+/**
+ * Character set of a RAW text buffer
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /**
+     * IBM PC code page, the power-on default of most ESC/POS printers
+     */
+    Cp437,
+    /**
+     * DOS Latin 1 code page, with the accented letters of western european languages
+     */
+    Cp850,
+    /**
+     * ISO 8859-1, each byte is the code point of the character
+     */
+    Latin1,
+    Utf8,
+    /**
+     * Japanese Shift-JIS (JIS X 0208 and half-width katakana)
+     */
+    ShiftJis,
+}
+
+impl Encoding {
+    /**
+     * Return the ESC/POS code page number selected with ESC t n, None when the printer
+     * is not switched with ESC t (UTF-8, Latin-1, or Shift-JIS which uses kanji mode)
+     */
+    pub fn escpos_code_page(&self) -> Option<u8> {
+        match self {
+            Encoding::Cp437 => Some(0),
+            Encoding::Cp850 => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Encode text in a code page, characters it cannot represent are written as '?'
+ */
+pub fn encode_text(text: &str, encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Utf8 => text.as_bytes().to_vec(),
+        Encoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(c as u32).unwrap_or(REPLACEMENT))
+            .collect(),
+        Encoding::Cp437 => encode_single_byte(text, &tables::CP437_HIGH),
+        Encoding::Cp850 => encode_single_byte(text, &tables::CP850_HIGH),
+        Encoding::ShiftJis => encode_shift_jis(text),
+    }
+}
+
+/**
+ * Encode with a code page that keeps ASCII and maps the bytes 0x80 to 0xFF with `high`
+ */
+fn encode_single_byte(text: &str, high: &[u16; 128]) -> Vec<u8> {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                return c as u8;
+            }
+            high.iter()
+                .position(|&mapped| mapped as u32 == c as u32)
+                .map_or(REPLACEMENT, |index| 0x80 + index as u8)
+        })
+        .collect()
+}
+
+fn encode_shift_jis(text: &str) -> Vec<u8> {
+    let double_bytes = shift_jis_double_bytes();
+    let mut bytes = Vec::with_capacity(text.len());
+
+    for c in text.chars() {
+        let code = c as u32;
+        if c.is_ascii() {
+            bytes.push(c as u8);
+        } else if (0xFF61..=0xFF9F).contains(&code) {
+            // Half-width katakana are single bytes 0xA1 to 0xDF
+            bytes.push((code - 0xFF61 + 0xA1) as u8);
+        } else {
+            match u16::try_from(code)
+                .ok()
+                .and_then(|code| double_bytes.get(&code))
+            {
+                Some(&[lead, trail]) => bytes.extend([lead, trail]),
+                None => bytes.push(REPLACEMENT),
+            }
+        }
+    }
+
+    bytes
+}
+
+/**
+ * Reverse of the Shift-JIS double byte table, built on first use
+ */
+fn shift_jis_double_bytes() -> &'static HashMap<u16, [u8; 2]> {
+    static DOUBLE_BYTES: OnceLock<HashMap<u16, [u8; 2]>> = OnceLock::new();
+
+    DOUBLE_BYTES.get_or_init(|| {
+        let leads = (0x81..=0x9F).chain(0xE0..=0xEF);
+        let trails: Vec<u8> = (0x40..=0x7E).chain(0x80..=0xFC).collect();
+
+        let mut map = HashMap::new();
+        for (row, lead) in leads.enumerate() {
+            for (column, &trail) in trails.iter().enumerate() {
+                let code = tables::SHIFT_JIS_DOUBLE[row * trails.len() + column];
+                if code != 0 {
+                    // Keep the first code of characters mapped twice
+                    map.entry(code).or_insert([lead, trail]);
+                }
+            }
+        }
+        map
+    })
+}
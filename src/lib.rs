@@ -17,21 +17,22 @@
 //!     // Get a printer by the name
 //!     let my_printer = get_printer_by_name("my_printer");
 //!     if my_printer.is_some() {
-//!         let job_id = my_printer.unwrap().print_file("notes.txt", PrinterJobOptions::none());
-//!         // Err("...") or Ok(())
+//!         let submission = my_printer.unwrap().print_file("notes.txt", PrinterJobOptions::none());
+//!         // Err("...") or Ok(JobSubmission)
 //!     }
 //!
 //!     // Use the default printer
 //!     let default_printer = get_default_printer();
 //!     if default_printer.is_some() {
-//!         let job_id = default_printer.unwrap().print("dlrow olleh".as_bytes(), PrinterJobOptions {
+//!         let submission = default_printer.unwrap().print("dlrow olleh".as_bytes(), PrinterJobOptions {
 //!             name: None,
 //!             raw_properties: &[
 //!                 ("document-format", "application/vnd.cups-raw"),
 //!                 ("copies", "2"),
 //!             ],
+//!             ..PrinterJobOptions::none()
 //!         });
-//!         // Err("...") or Ok(())
+//!         // Err("...") or Ok(JobSubmission)
 //!     }
 //! }
 //! ```
@@ -40,15 +41,89 @@ struct Platform;
 
 pub mod common;
 
+#[cfg(feature = "render")]
+pub mod render;
+
+#[cfg(feature = "zpl")]
+pub mod zpl;
+
+#[cfg(feature = "encoding")]
+pub mod encoding;
+
 #[cfg(target_family = "unix")]
 mod unix;
 
 #[cfg(target_family = "windows")]
 mod windows;
 
-use common::{traits::platform::{PlatformActions}};
-pub use common::base::printer::{Printer, PrinterState};
-pub use common::traits::platform::DeviceCaps;
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+mod unsupported;
+
+pub use common::base::content::PrintContent;
+pub use common::base::dialog::PrintDialogModel;
+pub use common::base::image::{PrintImageOptions, Rect, ScaleMode, StretchMode, TileOptions};
+pub use common::base::job::{
+    DataType, DocumentFormat, GlobalJobId, JobInfoLevel, JobSortOrder, JobSubmission, OnEmpty,
+    OnUnsupported, PrintResult, PrinterJob, PrinterJobCapabilities, PrinterJobOptions,
+    PrinterJobState, StreamOptions, ValidatedJobOptions,
+};
+pub use common::base::label::{LabelContent, LabelSpec};
+pub use common::base::media::{FormInfo, FormMargins, FormSpec};
+pub use common::base::permissions::{AccessControlEntry, PrinterRight};
+pub use common::base::printer::{PRINTER_NOT_FOUND, PortInfo, PortKind, Printer, PrinterState};
+use common::base::printer::{deduplicate_printers, sort_default_first};
+pub use common::base::registry::{Printers, PrintersWatch};
+pub use common::base::session::PrintSession;
+use common::traits::platform::PlatformActions;
+pub use common::traits::platform::{DeviceCaps, FeatureSet, MeasuredCaps, Unit};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/**
+ * How many print_to_many submissions run at the same time
+ */
+const PRINT_TO_MANY_THREADS: usize = 4;
+
+/**
+ * How many get_all_caps driver queries run at the same time
+ */
+const CAPS_QUERY_THREADS: usize = 4;
+
+/**
+ * How long the default printer name is reused before asking the system again
+ */
+const DEFAULT_PRINTER_TTL: Duration = Duration::from_secs(5);
+
+static DEFAULT_PRINTER_NAME: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/**
+ * How long get_printers_cached reuses the list on platforms without change notifications
+ */
+const PRINTERS_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/**
+ * Cached printers, with the change cookie and time they were enumerated at
+ */
+type PrintersCache = (Vec<Printer>, Option<u64>, Instant);
+
+static PRINTERS_CACHE: Mutex<Option<PrintersCache>> = Mutex::new(None);
+
+/**
+ * Return true if printing is implemented for the current platform
+ */
+pub fn is_supported() -> bool {
+    Platform::supported_features().printing
+}
+
+/**
+ * Return which features the printing backend of the current platform implements
+ */
+pub fn supported_features() -> FeatureSet {
+    Platform::supported_features()
+}
+
 /**
  * Return all available printers on a system
  */
@@ -57,15 +132,303 @@ pub fn get_printers() -> Vec<Printer> {
 }
 
 /**
- * If you know the printer name, you can try to get the printer directly
+ * Return all available printers, reusing the previous list until the spooler reports
+ * a change. On Windows the cache lives until a printer is added, deleted or changed on
+ * the local print server, elsewhere it is reused for 5 seconds. Safe to call from any
+ * thread, concurrent callers wait for a single enumeration
+ */
+pub fn get_printers_cached() -> Vec<Printer> {
+    let cookie = Platform::printers_change_cookie();
+
+    let Ok(mut cache) = PRINTERS_CACHE.lock() else {
+        return Platform::get_printers();
+    };
+
+    if let Some((printers, cached_cookie, cached_at)) = cache.as_ref() {
+        let fresh = match (cookie, cached_cookie) {
+            (Some(cookie), Some(cached_cookie)) => cookie == *cached_cookie,
+            _ => cached_at.elapsed() < PRINTERS_CACHE_TTL,
+        };
+        if fresh {
+            return printers.clone();
+        }
+    }
+
+    let printers = Platform::get_printers();
+    *cache = Some((printers.clone(), cookie, Instant::now()));
+    printers
+}
+
+/**
+ * Print the same buffer on several printers, returning one result per printer in the same order.
+ * Failures do not stop the other submissions, which run on up to 4 threads at a time
+ */
+pub fn print_to_many(
+    printer_names: &[&str],
+    buffer: &[u8],
+    options: PrinterJobOptions,
+) -> Vec<Result<JobSubmission, &'static str>> {
+    printer_names
+        .chunks(PRINT_TO_MANY_THREADS)
+        .flat_map(|names| {
+            thread::scope(|scope| {
+                let handles: Vec<_> = names
+                    .iter()
+                    .map(|name| {
+                        scope.spawn(move || {
+                            get_printer_by_name(name)
+                                .ok_or(PRINTER_NOT_FOUND)?
+                                .print(buffer, options)
+                        })
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or(Err("Print thread panicked")))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect()
+}
+
+/**
+ * Return the device caps of every printer keyed by system name, querying up to 4 drivers
+ * at a time. A printer whose driver fails or reports no resolution gets an error entry
+ * instead of failing the whole map
+ */
+pub fn get_all_caps() -> HashMap<String, Result<DeviceCaps, &'static str>> {
+    Platform::get_printers()
+        .chunks(CAPS_QUERY_THREADS)
+        .flat_map(|printers| {
+            thread::scope(|scope| {
+                let handles: Vec<_> = printers
+                    .iter()
+                    .map(|printer| (printer, scope.spawn(|| printer.get_printer_caps())))
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|(printer, handle)| {
+                        let caps = match handle.join() {
+                            Ok(caps) if caps.dpi_x > 0 && caps.dpi_y > 0 => Ok(caps),
+                            Ok(_) => Err("Printer did not report its device caps"),
+                            Err(_) => Err("Caps query thread panicked"),
+                        };
+                        (printer.system_name.clone(), caps)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect()
+}
+
+/**
+ * Return all available printers, or the error that prevented enumerating them
+ * (e.g. access denied or the spooler being unavailable), unlike get_printers
+ * which returns an empty list on failure
+ */
+pub fn try_get_printers() -> Result<Vec<Printer>, &'static str> {
+    Platform::try_get_printers()
+}
+
+/**
+ * System name of the PDF printer built into Windows 10 and later
+ */
+pub const PDF_PRINTER_NAME: &str = "Microsoft Print to PDF";
+
+/**
+ * Print content to a PDF file with the "Microsoft Print to PDF" printer, without its save
+ * dialog. The file is given to the spooler as the document output (DOCINFOW.lpszOutput for
+ * images printed through GDI, DOC_INFO_1W.pOutputFile for the other content), which is
+ * written instead of asking for a path. The driver only converts what reaches it as a
+ * document: images, text (TEXT datatype) and XPS (DocumentFormat::Xps). RAW bytes bypass
+ * the driver and are written to the file unchanged, so only already PDF content is useful
+ * as Raw or File. A single image can be written per file. Relative paths are resolved
+ * against the current directory, and an existing file is replaced
+ */
+pub fn print_to_pdf_file(
+    output_path: &str,
+    content: PrintContent,
+    options: PrinterJobOptions,
+) -> Result<PrintResult, &'static str> {
+    if matches!(&content, PrintContent::Images(images) if images.len() > 1) {
+        return Err("Only one image can be written to a PDF file");
+    }
+
+    let printer = Platform::get_printer_by_name(PDF_PRINTER_NAME).ok_or(PRINTER_NOT_FOUND)?;
+    // The spooler writes the file from its own process, with another current directory
+    let output_path = std::path::absolute(output_path).map_err(|_| "Invalid output path")?;
+    let output_path = output_path.to_str().ok_or("Invalid output path")?;
+
+    printer
+        .submit(
+            content,
+            PrinterJobOptions {
+                output_file: Some(output_path),
+                ..options
+            },
+        )?
+        .into_iter()
+        .next()
+        .ok_or("Nothing to print")
+}
+
+/**
+ * Return all available printers for a picker: the default printer first, then the
+ * others sorted by name, ignoring case
+ */
+pub fn get_printers_sorted() -> Vec<Printer> {
+    let default_name = Platform::get_default_printer().map(|p| p.system_name);
+    let mut printers = Platform::get_printers();
+    sort_default_first(&mut printers, default_name.as_deref());
+    printers
+}
+
+/**
+ * Return all available printers, keeping a single entry per queue. A local queue and a
+ * connection to it (\\this-host\share) or to the remote queue its port prints to
+ * (\\server\share) are one queue, listed once as the local entry. Names are compared
+ * ignoring case
+ */
+pub fn get_printers_deduplicated() -> Vec<Printer> {
+    let computer_name = Platform::get_computer_name();
+    deduplicate_printers(Platform::get_printers(), computer_name.as_deref())
+}
+
+/**
+ * Rename a local printer queue. Fails if no printer has `old_name` or another
+ * printer already uses `new_name`, compared ignoring case like Windows queue names,
+ * so only the case of a name can be changed
+ */
+pub fn rename_printer(old_name: &str, new_name: &str) -> Result<(), &'static str> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err("Printer name cannot be empty");
+    }
+
+    let printer = get_printer_by_exact_name(old_name).ok_or(PRINTER_NOT_FOUND)?;
+    let taken = Platform::get_printers()
+        .iter()
+        .any(|p| p.system_name != printer.system_name && p.matches_name(new_name));
+    if taken {
+        return Err("A printer with the new name already exists");
+    }
+
+    Platform::rename_printer(&printer.system_name, new_name)
+}
+
+/**
+ * Return the state and reasons of a printer by its system name without building the
+ * whole Printer, for polling a known printer (a single GetPrinterW call on Windows)
+ */
+pub fn get_printer_status(printer_name: &str) -> Option<(PrinterState, Vec<String>)> {
+    let (platform_state, mut state_reasons) = Platform::get_printer_status(printer_name)?;
+    if state_reasons.is_empty() {
+        state_reasons.push("none".to_string());
+    }
+
+    let state = PrinterState::from_platform_state(platform_state, state_reasons.join(",").as_str());
+    Some((state, state_reasons))
+}
+
+/**
+ * Check that the device behind a network printer accepts TCP connections, see Printer::ping
+ */
+pub fn ping_printer(printer_name: &str, timeout: Duration) -> Result<bool, &'static str> {
+    get_printer_by_name(printer_name)
+        .ok_or(PRINTER_NOT_FOUND)?
+        .ping(timeout)
+}
+
+/**
+ * Return the permissions of a printer by name (Windows only)
+ */
+pub fn get_printer_permissions(
+    printer_name: &str,
+) -> Result<Vec<AccessControlEntry>, &'static str> {
+    get_printer_by_name(printer_name)
+        .ok_or(PRINTER_NOT_FOUND)?
+        .get_permissions()
+}
+
+/**
+ * Share a printer by name on the network or stop sharing it, see Printer::set_shared
+ */
+pub fn set_printer_shared(
+    printer_name: &str,
+    shared: bool,
+    share_name: Option<&str>,
+) -> Result<(), &'static str> {
+    get_printer_by_name(printer_name)
+        .ok_or(PRINTER_NOT_FOUND)?
+        .set_shared(shared, share_name)
+}
+
+/**
+ * Return the ports of the local spooler (Windows only, empty on unix)
+ */
+pub fn get_ports() -> Vec<PortInfo> {
+    Platform::get_ports()
+}
+
+/**
+ * Return the print processor names of the local spooler (Windows only, empty on unix)
+ */
+pub fn get_print_processors() -> Vec<String> {
+    Platform::get_print_processors()
+}
+
+/**
+ * If you know the printer name, you can try to get the printer directly.
+ * The name is matched ignoring case and surrounding whitespace,
+ * use get_printer_by_exact_name to opt out
  */
 pub fn get_printer_by_name(printer_name: &str) -> Option<Printer> {
+    Platform::get_printer_by_name(printer_name.trim()).or_else(|| {
+        Platform::get_printers()
+            .into_iter()
+            .find(|p| p.matches_name(printer_name))
+    })
+}
+
+/**
+ * Get a printer by its exact name or system name
+ */
+pub fn get_printer_by_exact_name(printer_name: &str) -> Option<Printer> {
     Platform::get_printer_by_name(printer_name)
 }
 
 /**
- * Return the default system printer
+ * Return the default system printer.
+ * The default printer name is cached for a few seconds, so the common
+ * get_default_printer().print(...) pattern avoids enumerating all printers
  */
 pub fn get_default_printer() -> Option<Printer> {
-    Platform::get_default_printer()
+    let cached_name = DEFAULT_PRINTER_NAME
+        .lock()
+        .ok()
+        .and_then(|cache| cache.clone())
+        .filter(|(_, cached_at)| cached_at.elapsed() < DEFAULT_PRINTER_TTL)
+        .map(|(name, _)| name);
+
+    match cached_name.and_then(|name| Platform::get_printer_by_name(&name)) {
+        Some(printer) => Some(printer),
+        None => refresh_default(),
+    }
+}
+
+/**
+ * Read the default system printer again, replacing the cached name
+ */
+pub fn refresh_default() -> Option<Printer> {
+    let printer = Platform::get_default_printer();
+
+    if let Ok(mut cache) = DEFAULT_PRINTER_NAME.lock() {
+        *cache = printer
+            .as_ref()
+            .map(|p| (p.system_name.clone(), Instant::now()));
+    }
+
+    printer
 }
@@ -0,0 +1,69 @@
+//! Print pages rendered ahead of time (e.g. with `printpdf` and a rasterizer) as images,
+//! one image per page, keeping the physical size they were rendered for
+
+use crate::common::base::image::{PrintImageOptions, ScaleMode};
+use crate::common::base::job::PrintResult;
+use crate::common::base::printer::Printer;
+use crate::common::traits::platform::DeviceCaps;
+use image::DynamicImage;
+
+const MM_PER_INCH: f64 = 25.4;
+
+/**
+ * Physical size (width, height) in millimeters of an image rendered at `dpi`
+ */
+pub fn page_size_mm(page: &DynamicImage, dpi: f64) -> (f64, f64) {
+    (
+        page.width() as f64 / dpi * MM_PER_INCH,
+        page.height() as f64 / dpi * MM_PER_INCH,
+    )
+}
+
+/**
+ * Pixel size (width, height) an image rendered at `dpi` takes on the printer, using its DeviceCaps
+ */
+pub fn device_size_px(page: &DynamicImage, dpi: f64, caps: &DeviceCaps) -> (u32, u32) {
+    (
+        (page.width() as f64 * caps.dpi_x as f64 / dpi).round() as u32,
+        (page.height() as f64 * caps.dpi_y as f64 / dpi).round() as u32,
+    )
+}
+
+/**
+ * Print each rendered page with print_image, sizing the paper from the render DPI.
+ * Stops at the first page that fails and returns the results of the pages sent so far
+ */
+pub fn print_images(
+    printer: &Printer,
+    pages: Vec<DynamicImage>,
+    dpi: f64,
+    name: Option<&str>,
+) -> Result<Vec<PrintResult>, &'static str> {
+    if dpi <= 0.0 {
+        return Err("Render DPI must be greater than zero");
+    }
+
+    let mut results = Vec::with_capacity(pages.len());
+
+    for page in pages {
+        let (print_width, print_height) = page_size_mm(&page, dpi);
+        let result = printer.print_image_with_options(
+            page,
+            PrintImageOptions {
+                name,
+                print_width: Some(print_width),
+                print_height: Some(print_height),
+                scale_mode: ScaleMode::Fit,
+                ..PrintImageOptions::none()
+            },
+        )?;
+
+        let complete = result.is_complete();
+        results.push(result);
+        if !complete {
+            break;
+        }
+    }
+
+    Ok(results)
+}
@@ -1,36 +1,66 @@
-use cups::dests::get_dests;
-use std::str;
-use image::DynamicImage;
+use crate::DeviceCaps;
+use crate::common::base::media::{FormInfo, FormSpec, parse_media_size};
+use crate::common::base::permissions::AccessControlEntry;
 use crate::common::{
     base::{
-        job::{PrinterJob, PrinterJobOptions, PrinterJobState},
-        printer::{Printer, PrinterState},
+        image::PrintImageOptions,
+        job::{
+            JobInfoLevel, PrintResult, PrinterJob, PrinterJobCapabilities, PrinterJobOptions,
+            PrinterJobState, default_job_name, page_range,
+        },
+        printer::{PRINTER_NOT_FOUND, PortInfo, Printer, PrinterState},
     },
-    traits::platform::{PlatformActions, PlatformPrinterGetters},
+    traits::platform::{FeatureSet, PlatformActions, PlatformPrinterGetters},
 };
-use crate::DeviceCaps;
+use cups::dests::get_dests;
+use image::DynamicImage;
+use std::{io::Read, path::PathBuf, str};
 
 mod cups;
 mod image_print;
 mod utils;
 
 impl PlatformActions for crate::Platform {
+    // Every CUPS job is its own IPP request, there is no connection worth keeping open
+    type Session = ();
+
+    fn supported_features() -> FeatureSet {
+        FeatureSet {
+            printing: true,
+            image_printing: true,
+            job_control: true,
+            job_priority: true,
+            installation: false,
+        }
+    }
+
     fn get_printers() -> Vec<Printer> {
-        let dests = get_dests().unwrap_or_default();
+        Self::try_get_printers().unwrap_or_default()
+    }
+
+    fn try_get_printers() -> Result<Vec<Printer>, &'static str> {
+        let dests = cups::dests::try_get_dests()?;
         let printers = dests
             .iter()
             .map(|p| Printer::from_platform_printer_getters(p))
             .collect();
 
         cups::dests::free(dests);
-        printers
+        Ok(printers)
+    }
+
+    fn printers_change_cookie() -> Option<u64> {
+        // cupsd has no change notification short of an IPP subscription
+        None
     }
 
     fn get_printer_caps(printer_system_name: &str) -> DeviceCaps {
         let dests = get_dests().unwrap_or_default();
         let caps = dests
             .iter()
-            .find(|d| d.get_name() == printer_system_name || d.get_system_name() == printer_system_name)
+            .find(|d| {
+                d.get_name() == printer_system_name || d.get_system_name() == printer_system_name
+            })
             .map(build_device_caps)
             .unwrap_or_else(default_device_caps);
 
@@ -38,11 +68,36 @@ impl PlatformActions for crate::Platform {
         caps
     }
 
+    fn get_job_capabilities(printer_system_name: &str) -> PrinterJobCapabilities {
+        let dests = get_dests().unwrap_or_default();
+        let capabilities = dests
+            .iter()
+            .find(|d| {
+                d.get_name() == printer_system_name || d.get_system_name() == printer_system_name
+            })
+            .and_then(cups::attrs::query_job_capabilities)
+            .unwrap_or(PrinterJobCapabilities {
+                max_copies: None,
+                duplex: true,
+                collate: true,
+            });
+
+        cups::dests::free(dests);
+        capabilities
+    }
+
     fn print(
         printer_system_name: &str,
         buffer: &[u8],
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str> {
+    ) -> Result<PrintResult, &'static str> {
+        // CUPS would otherwise title the job with the temporary file path
+        let default_name = default_job_name();
+        let options = PrinterJobOptions {
+            name: options.name.or(Some(default_name.as_str())),
+            ..options
+        };
+
         let path = utils::file::save_tmp_file(buffer);
         if let Some(file_path) = path {
             Self::print_file(printer_system_name, file_path.to_str().unwrap(), options)
@@ -51,37 +106,67 @@ impl PlatformActions for crate::Platform {
         }
     }
 
+    fn open_session(_printer_system_name: &str) -> Result<Self::Session, &'static str> {
+        Ok(())
+    }
+
+    fn print_in_session(
+        _session: &Self::Session,
+        printer_system_name: &str,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Self::print(printer_system_name, buffer, options)
+    }
+
     fn print_file(
         printer_system_name: &str,
         file_path: &str,
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str> {
-        cups::jobs::print_file(
-            printer_system_name,
-            file_path,
-            options.name,
-            options.raw_properties,
-        )
+    ) -> Result<PrintResult, &'static str> {
+        let head = match options.document_format {
+            Some(_) => utils::file::read_file_head(file_path, 8),
+            None => vec![],
+        };
+
+        with_job_properties(printer_system_name, &options, &head, |raw_properties| {
+            cups::jobs::print_file(printer_system_name, file_path, options.name, raw_properties)
+        })
+        .map(PrintResult::from_job_id)
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        let default_name = default_job_name();
+        let job_name = options.name.unwrap_or(default_name.as_str());
+
+        // Read ahead only what format detection needs, the rest is streamed
+        let mut head = vec![];
+        (&mut *reader)
+            .take(8)
+            .read_to_end(&mut head)
+            .map_err(|_| "Failed to read the document")?;
+
+        with_job_properties(printer_system_name, &options, &head, |raw_properties| {
+            cups::jobs::print_stream(printer_system_name, job_name, &head, reader, raw_properties)
+        })
+        .map(PrintResult::from_job_id)
     }
 
     fn print_image(
         printer_system_name: &str,
         buffer: DynamicImage,
-        print_name: Option<&str>,
-        page_count: u32,
-        print_width: Option<f64>,
-        print_height: Option<f64>,
-    ) -> Result<u64, &'static str> {
-        image_print::print_image(
-            printer_system_name,
-            buffer,
-            print_name,
-            page_count,
-            print_width,
-            print_height,
-        )
-    }
-    
+        options: PrintImageOptions,
+    ) -> Result<PrintResult, &'static str> {
+        if options.output_file.is_some() {
+            return Err("Printing to a file is not supported by CUPS");
+        }
+        image_print::print_image(printer_system_name, buffer, options)
+    }
+
     fn get_printer_jobs(printer_name: &str, active_only: bool) -> Vec<PrinterJob> {
         cups::jobs::get_printer_jobs(printer_name, active_only)
             .unwrap_or_default()
@@ -90,6 +175,76 @@ impl PlatformActions for crate::Platform {
             .collect()
     }
 
+    fn get_printer_jobs_level(
+        printer_name: &str,
+        active_only: bool,
+        _level: JobInfoLevel,
+    ) -> Vec<PrinterJob> {
+        // cupsGetJobs always returns every job attribute
+        Self::get_printer_jobs(printer_name, active_only)
+    }
+
+    fn get_printer_jobs_paged(printer_name: &str, offset: u32, limit: u32) -> Vec<PrinterJob> {
+        // cupsGetJobs has no offset or limit, the whole history is fetched and then paged
+        let jobs = cups::jobs::get_printer_jobs(printer_name, false).unwrap_or_default();
+        jobs[page_range(jobs.len(), offset, limit)]
+            .iter()
+            .map(|j| PrinterJob::from_platform_printer_job_getters(j))
+            .collect()
+    }
+
+    fn get_spool_directory(_printer_name: &str) -> PathBuf {
+        PathBuf::from(CUPS_SPOOL_DIRECTORY)
+    }
+
+    fn get_ports() -> Vec<PortInfo> {
+        // CUPS has no spooler ports, printers are reached through their device uri
+        vec![]
+    }
+
+    fn get_forms(_printer_name: &str) -> Vec<FormInfo> {
+        // Forms are a spooler concept, CUPS exposes paper sizes as media names instead
+        vec![]
+    }
+
+    fn add_form(_printer_name: &str, _form: &FormSpec) -> Result<(), &'static str> {
+        Err("Forms are not supported by CUPS")
+    }
+
+    fn delete_form(_printer_name: &str, _form_name: &str) -> Result<(), &'static str> {
+        Err("Forms are not supported by CUPS")
+    }
+
+    fn get_printer_permissions(
+        _printer_name: &str,
+    ) -> Result<Vec<AccessControlEntry>, &'static str> {
+        // CUPS controls access with cupsd.conf policies, not per queue security descriptors
+        Err("Printer permissions are not supported by CUPS")
+    }
+
+    fn get_print_processors() -> Vec<String> {
+        vec![]
+    }
+
+    fn get_computer_name() -> Option<String> {
+        // CUPS queues are shared by their ipp uri, there are no \\host\share paths
+        None
+    }
+
+    fn rename_printer(_printer_name: &str, _new_name: &str) -> Result<(), &'static str> {
+        // CUPS has no rename operation, a queue must be recreated with lpadmin
+        Err("Renaming printers is not supported by CUPS")
+    }
+
+    fn set_printer_shared(
+        printer_name: &str,
+        shared: bool,
+        _share_name: Option<&str>,
+    ) -> Result<(), &'static str> {
+        // CUPS shares a queue under its own name
+        cups::attrs::set_printer_shared(printer_name, shared)
+    }
+
     fn get_default_printer() -> Option<Printer> {
         let dests = get_dests().unwrap_or_default();
         let dest = dests
@@ -112,6 +267,18 @@ impl PlatformActions for crate::Platform {
         dest
     }
 
+    fn get_printer_status(printer_name: &str) -> Option<(u64, Vec<String>)> {
+        // The state is part of the destination options, there is no cheaper CUPS query
+        let dests = get_dests().unwrap_or_default();
+        let status = dests
+            .iter()
+            .find(|d| d.get_name() == printer_name || d.get_system_name() == printer_name)
+            .map(|d| (d.get_state(), d.get_state_reasons()));
+
+        cups::dests::free(dests);
+        status
+    }
+
     fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
         if state_reasons.contains("offline-report") {
             return PrinterState::OFFLINE;
@@ -152,11 +319,106 @@ impl PlatformActions for crate::Platform {
         if result {
             Ok(())
         } else {
-            Err("cups method failed")
+            Err(cups_method_error(printer_name))
+        }
+    }
+
+    fn job_exists(printer_name: &str, job_id: u64) -> bool {
+        // Finished jobs stay in the CUPS history until they are purged
+        Self::get_printer_jobs(printer_name, false)
+            .iter()
+            .any(|j| j.id == job_id)
+    }
+
+    fn set_job_priority(
+        printer_name: &str,
+        job_id: u64,
+        priority: u32,
+    ) -> Result<(), &'static str> {
+        if !(CUPS_MIN_JOB_PRIORITY..=CUPS_MAX_JOB_PRIORITY).contains(&priority) {
+            return Err("Job priority out of range (1-100)");
+        }
+
+        if cups::jobs::set_job_priority(printer_name, job_id as i32, priority as i32) {
+            Ok(())
+        } else {
+            Err(cups_method_error(printer_name))
         }
     }
 }
 
+const CUPS_SPOOL_DIRECTORY: &str = "/var/spool/cups";
+
+/**
+ * Build the CUPS job options from the job options, `head` being the first bytes of the
+ * document for format detection, and submit the job with them
+ */
+fn with_job_properties(
+    printer_system_name: &str,
+    options: &PrinterJobOptions,
+    head: &[u8],
+    submit: impl FnOnce(&[(&str, &str)]) -> Result<u64, &'static str>,
+) -> Result<u64, &'static str> {
+    if options.output_file.is_some() {
+        return Err("Printing to a file is not supported by CUPS");
+    }
+
+    let copies: String;
+    let mut raw_properties = options.raw_properties.to_vec();
+
+    // Windows form names are passed to CUPS as media names
+    if let Some(form) = options.get_raw_property("form") {
+        raw_properties.retain(|(key, _)| *key != "form");
+        if options.get_raw_property("media").is_none() {
+            raw_properties.push(("media", form));
+        }
+    }
+
+    // Held jobs wait in the queue until released
+    if options.get_raw_property("hold").is_some() {
+        raw_properties.retain(|(key, _)| *key != "hold");
+        if options.is_held() && options.get_raw_property("job-hold-until").is_none() {
+            raw_properties.push(("job-hold-until", "indefinite"));
+        }
+    }
+
+    if let Some(format) = options.document_format {
+        raw_properties.retain(|(key, _)| *key != "document-format");
+        raw_properties.push(("document-format", format.resolve(head).mime_type()));
+    }
+
+    if options.has_device_options() {
+        let validated =
+            crate::Platform::get_job_capabilities(printer_system_name).validate(options)?;
+        copies = validated.copies.to_string();
+        raw_properties.retain(|(key, _)| !validated.clamped.contains(key) && *key != "copies");
+
+        raw_properties.push(("copies", copies.as_str()));
+        if validated.clamped.contains(&"sides") {
+            raw_properties.push(("sides", "one-sided"));
+        }
+        if validated.clamped.contains(&"collate") {
+            raw_properties.push(("collate", "false"));
+        }
+    }
+
+    submit(&raw_properties)
+}
+
+/**
+ * Tell a missing printer apart from other failures of the job IPP operations,
+ * whose not-found status could also mean the job is gone
+ */
+fn cups_method_error(printer_name: &str) -> &'static str {
+    if crate::Platform::get_printer_by_name(printer_name).is_none() {
+        PRINTER_NOT_FOUND
+    } else {
+        "cups method failed"
+    }
+}
+
+const CUPS_MIN_JOB_PRIORITY: u32 = 1;
+const CUPS_MAX_JOB_PRIORITY: u32 = 100;
 const DEFAULT_DPI: i32 = 300;
 const MM_PER_INCH: f64 = 25.4;
 
@@ -279,93 +541,13 @@ fn parse_resolution_value(value: &str) -> Option<(i32, i32)> {
     }
 
     let dpi = parse_first_i32(text)?;
-    if dpi > 0 {
-        Some((dpi, dpi))
-    } else {
-        None
-    }
+    if dpi > 0 { Some((dpi, dpi)) } else { None }
 }
 
 fn parse_media_size_mm(value: &str) -> Option<(f64, f64)> {
-    let text = value.trim().to_ascii_lowercase();
-    if text.is_empty() {
-        return None;
-    }
-
-    if let Some(size) = parse_dimension_suffix_mm(text.as_str()) {
-        return Some(size);
-    }
-
-    if let Some(size) = parse_dimension_suffix_in(text.as_str()) {
-        return Some(size);
-    }
-
-    parse_named_media_mm(text.as_str())
-}
-
-fn parse_dimension_suffix_mm(text: &str) -> Option<(f64, f64)> {
-    let base = text.strip_suffix("mm")?;
-    let (w, h) = parse_xy_numbers(base)?;
-    Some((w, h))
-}
-
-fn parse_dimension_suffix_in(text: &str) -> Option<(f64, f64)> {
-    let base = text.strip_suffix("in")?;
-    let (w, h) = parse_xy_numbers(base)?;
-    Some((w * MM_PER_INCH, h * MM_PER_INCH))
-}
-
-fn parse_xy_numbers(text: &str) -> Option<(f64, f64)> {
-    let (left, right) = text.rsplit_once('x')?;
-    let w = parse_trailing_f64(left)?;
-    let h = parse_leading_f64(right)?;
-    if w > 0.0 && h > 0.0 {
-        Some((w, h))
-    } else {
-        None
-    }
-}
-
-fn parse_trailing_f64(text: &str) -> Option<f64> {
-    let end = text
-        .chars()
-        .rev()
-        .take_while(|c| c.is_ascii_digit() || *c == '.')
-        .count();
-    if end == 0 {
-        return None;
-    }
-
-    let start = text.len().checked_sub(end)?;
-    text[start..].parse::<f64>().ok()
-}
-
-fn parse_leading_f64(text: &str) -> Option<f64> {
-    let len = text
-        .chars()
-        .take_while(|c| c.is_ascii_digit() || *c == '.')
-        .count();
-    if len == 0 {
-        return None;
-    }
-
-    text[..len].parse::<f64>().ok()
-}
-
-fn parse_named_media_mm(text: &str) -> Option<(f64, f64)> {
-    let normalized: String = text
-        .chars()
-        .filter(|c| *c != '-' && *c != '_' && !c.is_ascii_whitespace())
-        .collect();
-
-    match normalized.as_str() {
-        "a3" => Some((297.0, 420.0)),
-        "a4" => Some((210.0, 297.0)),
-        "a5" => Some((148.0, 210.0)),
-        "letter" => Some((215.9, 279.4)),
-        "legal" => Some((215.9, 355.6)),
-        _ => None,
-    }
+    parse_media_size(value)
+        .ok()
+        .map(|size| (size.width_mm, size.height_mm))
 }
 
 fn parse_first_i32(text: &str) -> Option<i32> {
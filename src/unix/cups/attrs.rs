@@ -1,13 +1,21 @@
 use libc::{c_char, c_int};
 use std::{ffi::CString, os::raw::c_void, ptr};
 
+use crate::common::base::job::PrinterJobCapabilities;
+use crate::common::base::printer::PRINTER_NOT_FOUND;
 use crate::unix::cups::dests::CupsDestT;
 
 const CUPS_IPP_OK: c_int = 0x0000;
 const CUPS_IPP_OP_GET_PRINTER_ATTRIBUTES: c_int = 0x000B;
+const CUPS_IPP_OP_CUPS_ADD_MODIFY_PRINTER: c_int = 0x4003;
+const CUPS_IPP_STATUS_FORBIDDEN: c_int = 0x0401;
+const CUPS_IPP_STATUS_NOT_AUTHENTICATED: c_int = 0x0402;
+const CUPS_IPP_STATUS_NOT_AUTHORIZED: c_int = 0x0403;
+const CUPS_IPP_STATUS_NOT_FOUND: c_int = 0x0406;
 
 const CUPS_IPP_TAG_ZERO: c_int = 0x00;
 const CUPS_IPP_TAG_OPERATION: c_int = 0x01;
+const CUPS_IPP_TAG_PRINTER: c_int = 0x04;
 const CUPS_IPP_TAG_KEYWORD: c_int = 0x44;
 const CUPS_IPP_TAG_URI: c_int = 0x45;
 const CUPS_IPP_TAG_RESOLUTION: c_int = 0x32;
@@ -43,6 +51,13 @@ unsafe extern "C" {
         values: *const *const c_char,
     );
 
+    unsafe fn ippAddBoolean(
+        req: *mut c_void,
+        group: c_int,
+        name: *const c_char,
+        value: c_char,
+    ) -> *mut c_void;
+
     unsafe fn ippFindAttribute(
         response: *mut c_void,
         name: *const c_char,
@@ -55,6 +70,7 @@ unsafe extern "C" {
         idx: c_int,
         language: *mut *const c_char,
     ) -> *const c_char;
+    unsafe fn ippGetRange(attr: *mut c_void, idx: c_int, upper: *mut c_int) -> c_int;
     unsafe fn ippGetResolution(
         attr: *mut c_void,
         idx: c_int,
@@ -71,9 +87,135 @@ pub fn query_printer_dpi(dest: &CupsDestT) -> Option<(i32, i32)> {
         }
 
         add_printer_uri(req, dest);
-        add_requested_attributes(req);
+        add_requested_attributes(
+            req,
+            &[
+                "printer-resolution-default",
+                "printer-resolution-supported",
+                "urf-supported",
+            ],
+        );
+
+        let response = send_request(req)?;
+
+        let dpi = query_default_resolution(response)
+            .or_else(|| query_highest_supported_resolution(response))
+            .or_else(|| query_urf_resolution(response));
+
+        ippDelete(response);
+        dpi
+    }
+}
+
+/**
+ * Query copies-supported, sides-supported and multiple-document-handling-supported
+ */
+pub fn query_job_capabilities(dest: &CupsDestT) -> Option<PrinterJobCapabilities> {
+    unsafe {
+        let req = ippNewRequest(CUPS_IPP_OP_GET_PRINTER_ATTRIBUTES);
+        if req.is_null() {
+            return None;
+        }
+
+        add_printer_uri(req, dest);
+        add_requested_attributes(
+            req,
+            &[
+                "copies-supported",
+                "sides-supported",
+                "multiple-document-handling-supported",
+            ],
+        );
+
+        let response = send_request(req)?;
+
+        let max_copies = find_attribute(response, "copies-supported").and_then(|attr| {
+            let mut upper = 0;
+            ippGetRange(attr, 0, &mut upper);
+            if upper > 0 { Some(upper as u32) } else { None }
+        });
+
+        // Printers that do not report sides or collate support are left to decide themselves
+        let duplex = find_attribute(response, "sides-supported")
+            .map(|attr| get_strings(attr).iter().any(|v| v.starts_with("two-sided")))
+            .unwrap_or(true);
+        let collate = find_attribute(response, "multiple-document-handling-supported")
+            .map(|attr| {
+                get_strings(attr)
+                    .iter()
+                    .any(|v| v == "separate-documents-collated-copies")
+            })
+            .unwrap_or(true);
+
+        ippDelete(response);
+
+        Some(PrinterJobCapabilities {
+            max_copies,
+            duplex,
+            collate,
+        })
+    }
+}
+
+/**
+ * Share or unshare a local queue by setting printer-is-shared with CUPS-Add-Modify-Printer,
+ * which cupsd only accepts from administrators
+ */
+pub fn set_printer_shared(printer_name: &str, shared: bool) -> Result<(), &'static str> {
+    let uri = CString::new(format!("ipp://localhost/printers/{printer_name}"))
+        .map_err(|_| "Invalid printer name")?;
+    let (Ok(uri_name), Ok(shared_name), Ok(resource)) = (
+        CString::new("printer-uri"),
+        CString::new("printer-is-shared"),
+        CString::new("/admin/"),
+    ) else {
+        return Err("Invalid attribute name");
+    };
+
+    unsafe {
+        let req = ippNewRequest(CUPS_IPP_OP_CUPS_ADD_MODIFY_PRINTER);
+        if req.is_null() {
+            return Err("ippNewRequest failed");
+        }
 
-        let resource = CString::new("/").ok()?;
+        ippAddString(
+            req,
+            CUPS_IPP_TAG_OPERATION,
+            CUPS_IPP_TAG_URI,
+            uri_name.as_ptr(),
+            ptr::null(),
+            uri.as_ptr(),
+        );
+        ippAddBoolean(
+            req,
+            CUPS_IPP_TAG_PRINTER,
+            shared_name.as_ptr(),
+            shared as c_char,
+        );
+
+        // cupsDoRequest frees the request
+        let response = cupsDoRequest(ptr::null_mut(), req, resource.as_ptr());
+        let status = cupsLastError();
+        if !response.is_null() {
+            ippDelete(response);
+        }
+
+        match status {
+            CUPS_IPP_OK => Ok(()),
+            CUPS_IPP_STATUS_FORBIDDEN
+            | CUPS_IPP_STATUS_NOT_AUTHENTICATED
+            | CUPS_IPP_STATUS_NOT_AUTHORIZED => {
+                Err("Sharing printers requires administrator privileges")
+            }
+            CUPS_IPP_STATUS_NOT_FOUND => Err(PRINTER_NOT_FOUND),
+            _ => Err("cups method failed"),
+        }
+    }
+}
+
+unsafe fn send_request(req: *mut c_void) -> Option<*mut c_void> {
+    let resource = CString::new("/").ok()?;
+    unsafe {
         let response = cupsDoRequest(ptr::null_mut(), req, resource.as_ptr());
         if response.is_null() || cupsLastError() != CUPS_IPP_OK {
             if !response.is_null() {
@@ -81,14 +223,32 @@ pub fn query_printer_dpi(dest: &CupsDestT) -> Option<(i32, i32)> {
             }
             return None;
         }
+        Some(response)
+    }
+}
 
-        let dpi = query_default_resolution(response)
-            .or_else(|| query_highest_supported_resolution(response))
-            .or_else(|| query_urf_resolution(response));
+unsafe fn find_attribute(response: *mut c_void, name: &str) -> Option<*mut c_void> {
+    let name = CString::new(name).ok()?;
+    let attr = unsafe { ippFindAttribute(response, name.as_ptr(), CUPS_IPP_TAG_ZERO) };
+    if attr.is_null() { None } else { Some(attr) }
+}
 
-        ippDelete(response);
-        dpi
-    }
+unsafe fn get_strings(attr: *mut c_void) -> Vec<String> {
+    let count = unsafe { ippGetCount(attr) };
+    (0..count)
+        .filter_map(|idx| {
+            let value_ptr = unsafe { ippGetString(attr, idx, ptr::null_mut()) };
+            if value_ptr.is_null() {
+                None
+            } else {
+                Some(
+                    unsafe { std::ffi::CStr::from_ptr(value_ptr) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        })
+        .collect()
 }
 
 unsafe fn add_printer_uri(req: *mut c_void, dest: &CupsDestT) {
@@ -107,18 +267,12 @@ unsafe fn add_printer_uri(req: *mut c_void, dest: &CupsDestT) {
     }
 }
 
-unsafe fn add_requested_attributes(req: *mut c_void) {
+unsafe fn add_requested_attributes(req: *mut c_void, attributes: &[&str]) {
     let name = match CString::new("requested-attributes") {
         Ok(v) => v,
         Err(_) => return,
     };
 
-    let attributes = [
-        "printer-resolution-default",
-        "printer-resolution-supported",
-        "urf-supported",
-    ];
-
     let values: Vec<CString> = attributes
         .iter()
         .filter_map(|item| CString::new(*item).ok())
@@ -204,11 +358,7 @@ unsafe fn query_urf_resolution(response: *mut c_void) -> Option<(i32, i32)> {
         }
     }
 
-    if best > 0 {
-        Some((best, best))
-    } else {
-        None
-    }
+    if best > 0 { Some((best, best)) } else { None }
 }
 
 unsafe fn parse_resolution_attr(attr: *mut c_void, pick_highest: bool) -> Option<(i32, i32)> {
@@ -288,11 +438,7 @@ fn parse_resolution_value(value: &str) -> Option<(i32, i32)> {
     }
 
     let dpi = parse_first_i32(text)?;
-    if dpi > 0 {
-        Some((dpi, dpi))
-    } else {
-        None
-    }
+    if dpi > 0 { Some((dpi, dpi)) } else { None }
 }
 
 fn parse_first_i32(text: &str) -> Option<i32> {
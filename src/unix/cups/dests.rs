@@ -4,10 +4,14 @@ use crate::{
 use libc::{c_char, c_int};
 use std::{ffi::CString, ptr, slice};
 
+const CUPS_IPP_STATUS_ERROR: c_int = 0x0400;
+const CUPS_IPP_SERVICE_UNAVAILABLE: c_int = 0x0503;
+
 #[link(name = "cups")]
 unsafe extern "C" {
     fn cupsGetDests(dests: *mut *mut CupsDestT) -> c_int;
     fn cupsFreeDests(num_dests: c_int, dests: *const CupsDestT);
+    fn cupsLastError() -> c_int;
     fn cupsGetOption(
         name: *const c_char,
         num_options: c_int,
@@ -62,7 +66,7 @@ impl CupsDestT {
     pub(crate) fn get_option_value(&self, key: &str) -> String {
         self.get_option(key)
     }
-    
+
     pub fn get_is_default(&self) -> bool {
         self.is_default == 1
     }
@@ -127,6 +131,11 @@ impl PlatformPrinterGetters for CupsDestT {
     fn get_data_type(&self) -> String {
         self.get_option("media")
     }
+
+    fn get_share_name(&self) -> String {
+        // CUPS shares queues over IPP by their name, there is no separate share name
+        "".to_string()
+    }
 }
 
 /**
@@ -145,6 +154,21 @@ pub fn get_dests() -> Option<&'static [CupsDestT]> {
     }
 }
 
+/**
+ * Return the printer destinations, telling apart having none from a failed request
+ */
+pub fn try_get_dests() -> Result<&'static [CupsDestT], &'static str> {
+    if let Some(dests) = get_dests() {
+        return Ok(dests);
+    }
+
+    match unsafe { cupsLastError() } {
+        CUPS_IPP_SERVICE_UNAVAILABLE => Err("CUPS server unavailable"),
+        status if status >= CUPS_IPP_STATUS_ERROR => Err("Failed to get CUPS destinations"),
+        _ => Ok(&[]),
+    }
+}
+
 /**
  * Free dests memory
  */
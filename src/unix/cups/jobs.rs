@@ -1,23 +1,38 @@
 use libc::{c_char, c_int, time_t};
-use std::{os::raw::c_void, ptr, slice, time::SystemTime};
+use std::{
+    io::{ErrorKind, Read},
+    os::raw::c_void,
+    ptr, slice,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
-    common::{base::options::OptionsCollection, traits::platform::PlatformPrinterJobGetters},
-    unix::utils::{
-        date::time_t_to_system_time,
-        strings::{c_char_to_string, str_to_cstring},
+    common::{
+        base::{options::OptionsCollection, printer::PRINTER_NOT_FOUND},
+        time::system_time_from_unix,
+        traits::platform::PlatformPrinterJobGetters,
     },
+    unix::utils::strings::{c_char_to_string, str_to_cstring},
 };
 
 const CUPS_IPP_OK: c_int = 0x0000;
+const CUPS_IPP_NOT_FOUND: c_int = 0x0406;
 const CUPS_IPP_TAG_URI: c_int = 0x45;
 const CUPS_IPP_TAG_INTEGER: c_int = 0x21;
 const CUPS_IPP_TAG_OPERATION: c_int = 0x01;
+const CUPS_IPP_TAG_JOB: c_int = 0x02;
 
 const CUPS_IPP_OP_HOLD_JOB: c_int = 12;
 const CUPS_IPP_OP_CANCEL_JOB: c_int = 8;
 const CUPS_IPP_OP_RELEASE_JOB: c_int = 13;
 const CUPS_IPP_OP_RESTART_JOB: c_int = 14;
+const CUPS_IPP_OP_SET_JOB_ATTRIBUTES: c_int = 20;
+
+// IPP successful status codes are 0x0000 to 0x00FF
+const CUPS_IPP_SUCCESSFUL_MAX: c_int = 0x00FF;
+const CUPS_HTTP_STATUS_CONTINUE: c_int = 100;
+const CUPS_FORMAT_AUTO: &str = "application/octet-stream";
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
 
 #[link(name = "cups")]
 unsafe extern "C" {
@@ -29,6 +44,31 @@ unsafe extern "C" {
         options: *const CupsOptionT,
     ) -> c_int;
 
+    unsafe fn cupsCreateJob(
+        http: *mut c_void,
+        name: *const c_char,
+        title: *const c_char,
+        num_options: c_int,
+        options: *const CupsOptionT,
+    ) -> c_int;
+
+    unsafe fn cupsStartDocument(
+        http: *mut c_void,
+        name: *const c_char,
+        job_id: c_int,
+        docname: *const c_char,
+        format: *const c_char,
+        last_document: c_int,
+    ) -> c_int;
+
+    unsafe fn cupsWriteRequestData(
+        http: *mut c_void,
+        buffer: *const c_char,
+        length: usize,
+    ) -> c_int;
+
+    unsafe fn cupsFinishDocument(http: *mut c_void, name: *const c_char) -> c_int;
+
     unsafe fn cupsGetJobs(
         jobs: *mut *mut CupsJobsS,
         name: *const c_char,
@@ -104,20 +144,33 @@ impl PlatformPrinterJobGetters for CupsJobsS {
         c_char_to_string(self.dest)
     }
 
+    fn get_user(&self) -> String {
+        c_char_to_string(self.user)
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.priority as u32
+    }
+
+    fn get_size(&self) -> u64 {
+        // CUPS reports the job size in kilobytes
+        self.size.max(0) as u64 * 1024
+    }
+
     fn get_media_type(&self) -> String {
         c_char_to_string(self.format)
     }
 
     fn get_created_at(&self) -> SystemTime {
-        time_t_to_system_time(self.creation_time).unwrap()
+        system_time_from_unix(self.creation_time).unwrap_or(UNIX_EPOCH)
     }
 
     fn get_processed_at(&self) -> Option<SystemTime> {
-        time_t_to_system_time(self.processing_time)
+        system_time_from_unix(self.processing_time)
     }
 
     fn get_completed_at(&self) -> Option<SystemTime> {
-        time_t_to_system_time(self.completed_time)
+        system_time_from_unix(self.completed_time)
     }
 }
 
@@ -172,6 +225,9 @@ pub fn print_file(
         );
 
         if result == 0 {
+            if cupsLastError() == CUPS_IPP_NOT_FOUND {
+                return Err(PRINTER_NOT_FOUND);
+            }
             Err("cupsPrintFile failed")
         } else {
             Ok(result as u64)
@@ -179,6 +235,107 @@ pub fn print_file(
     }
 }
 
+/**
+ * Create a job and send it the data read from `reader` after `head`, without a temporary
+ * file. The document-format option is used as the document format
+ */
+pub fn print_stream(
+    printer_name: &str,
+    job_name: &str,
+    head: &[u8],
+    reader: &mut dyn Read,
+    raw_options: &[(&str, &str)],
+) -> Result<u64, &'static str> {
+    let format = raw_options
+        .iter()
+        .find(|(key, _)| *key == "document-format")
+        .map_or(CUPS_FORMAT_AUTO, |(_, value)| *value);
+    let raw_options: Vec<(&str, &str)> = raw_options
+        .iter()
+        .filter(|(key, _)| *key != "document-format")
+        .copied()
+        .collect();
+
+    unsafe {
+        let printer = &str_to_cstring(printer_name);
+        let title = str_to_cstring(job_name);
+        let format = str_to_cstring(format);
+
+        let options = OptionsCollection::new(&raw_options, |(key, value)| {
+            let key = str_to_cstring(key);
+            let value = str_to_cstring(value);
+            let option = CupsOptionT {
+                name: key.as_ptr(),
+                value: value.as_ptr(),
+            };
+            ((key, value), option)
+        });
+
+        let job_id = cupsCreateJob(
+            ptr::null_mut(),
+            printer.as_ptr(),
+            title.as_ptr(),
+            options.size as c_int,
+            options.as_ptr(),
+        );
+        if job_id == 0 {
+            if cupsLastError() == CUPS_IPP_NOT_FOUND {
+                return Err(PRINTER_NOT_FOUND);
+            }
+            return Err("cupsCreateJob failed");
+        }
+
+        if cupsStartDocument(
+            ptr::null_mut(),
+            printer.as_ptr(),
+            job_id,
+            title.as_ptr(),
+            format.as_ptr(),
+            1,
+        ) != CUPS_HTTP_STATUS_CONTINUE
+        {
+            cancel_job(printer_name, job_id);
+            return Err("cupsStartDocument failed");
+        }
+
+        let write = |chunk: &[u8]| {
+            cupsWriteRequestData(
+                ptr::null_mut(),
+                chunk.as_ptr() as *const c_char,
+                chunk.len(),
+            ) == CUPS_HTTP_STATUS_CONTINUE
+        };
+
+        let mut error = if head.is_empty() || write(head) {
+            None
+        } else {
+            Some("cupsWriteRequestData failed")
+        };
+        let mut chunk = vec![0u8; STREAM_CHUNK_SIZE];
+        while error.is_none() {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) if !write(&chunk[..read]) => error = Some("cupsWriteRequestData failed"),
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => error = Some("Failed to read the document"),
+            }
+        }
+
+        // The request has to be finished even on failure, a partial job is then cancelled
+        let status = cupsFinishDocument(ptr::null_mut(), printer.as_ptr());
+        if let Some(error) = error {
+            cancel_job(printer_name, job_id);
+            return Err(error);
+        }
+        if status > CUPS_IPP_SUCCESSFUL_MAX {
+            return Err("cupsFinishDocument failed");
+        }
+
+        Ok(job_id as u64)
+    }
+}
+
 /**
  * Send cancel job request to cups
  */
@@ -207,10 +364,34 @@ pub fn cancel_job(printer_name: &str, job_id: i32) -> bool {
     do_request(printer_name, job_id, CUPS_IPP_OP_CANCEL_JOB)
 }
 
+/**
+ * Send set job attributes request to cups changing the job priority
+ */
+pub fn set_job_priority(printer_name: &str, job_id: i32, priority: i32) -> bool {
+    do_request_with_job_attributes(
+        printer_name,
+        job_id,
+        CUPS_IPP_OP_SET_JOB_ATTRIBUTES,
+        &[("job-priority", priority)],
+    )
+}
+
 /**
  * Send request op to cups
  */
 fn do_request(printer_name: &str, job_id: i32, op: i32) -> bool {
+    do_request_with_job_attributes(printer_name, job_id, op, &[])
+}
+
+/**
+ * Send request op to cups with additional integer job attributes
+ */
+fn do_request_with_job_attributes(
+    printer_name: &str,
+    job_id: i32,
+    op: i32,
+    job_attributes: &[(&str, i32)],
+) -> bool {
     unsafe {
         let req = ippNewRequest(op);
         if req.is_null() {
@@ -239,6 +420,17 @@ fn do_request(printer_name: &str, job_id: i32, op: i32) -> bool {
             job_id,
         );
 
+        for (name, value) in job_attributes {
+            let name = str_to_cstring(name);
+            ippAddInteger(
+                req,
+                CUPS_IPP_TAG_JOB,
+                CUPS_IPP_TAG_INTEGER,
+                name.as_ptr(),
+                *value,
+            );
+        }
+
         let resource = &str_to_cstring("/");
         let response = cupsDoRequest(ptr::null_mut(), req, resource.as_ptr());
         let status = cupsLastError();
@@ -1,28 +1,28 @@
 use image::DynamicImage;
 
 use crate::common::{
-    base::job::PrinterJobOptions,
+    base::{image::PrintImageOptions, job::PrinterJobOptions},
     traits::platform::PlatformActions,
 };
 
 pub fn print_image(
     printer_system_name: &str,
     image: DynamicImage,
-    print_name: Option<&str>,
-    page_count: u32,
-    print_width: Option<f64>,
-    print_height: Option<f64>,
+    options: PrintImageOptions,
 ) -> Result<u64, &'static str> {
     let image_bytes = super::image_to_png_bytes(&image)?;
-    let copies = super::normalize_page_count(page_count).to_string();
+    let copies = super::normalize_page_count(options.page_count).to_string();
 
     let mut owned_options = vec![
         (String::from("document-format"), String::from("image/png")),
         (String::from("copies"), copies),
-        (String::from("print-scaling"), String::from("fit")),
+        (
+            String::from("print-scaling"),
+            String::from(super::print_scaling(options.scale_mode)),
+        ),
     ];
 
-    if let (Some(width), Some(height)) = (print_width, print_height)
+    if let (Some(width), Some(height)) = (options.print_width, options.print_height)
         && let Some(media) = super::media_custom_mm(width, height)
     {
         owned_options.push((String::from("media"), media));
@@ -37,8 +37,10 @@ pub fn print_image(
         printer_system_name,
         &image_bytes,
         PrinterJobOptions {
-            name: print_name,
+            name: options.name,
             raw_properties: &raw_properties,
+            ..PrinterJobOptions::none()
         },
     )
+    .map(|result| result.job_id)
 }
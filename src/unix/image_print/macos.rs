@@ -1,7 +1,7 @@
 use image::DynamicImage;
 
 use crate::common::{
-    base::job::PrinterJobOptions,
+    base::{image::PrintImageOptions, job::PrinterJobOptions},
     traits::platform::{PlatformActions, PlatformPrinterGetters},
 };
 
@@ -11,10 +11,7 @@ const MM_PER_INCH: f64 = 25.4;
 pub fn print_image(
     printer_system_name: &str,
     image: DynamicImage,
-    print_name: Option<&str>,
-    page_count: u32,
-    print_width: Option<f64>,
-    print_height: Option<f64>,
+    options: PrintImageOptions,
 ) -> Result<u64, &'static str> {
     let printer_dpi = query_printer_dpi(printer_system_name);
     let printer_resolution = printer_dpi.map(format_resolution);
@@ -24,8 +21,14 @@ pub fn print_image(
         .ok_or("Failed to create temp file")?;
     let png_path = png_path.to_str().ok_or("Failed to create temp file path")?;
 
-    let copies = super::normalize_page_count(page_count).to_string();
-    let custom_media = resolve_custom_media(&image, print_width, print_height, printer_dpi);
+    let copies = super::normalize_page_count(options.page_count).to_string();
+    let custom_media = resolve_custom_media(
+        &image,
+        options.print_width,
+        options.print_height,
+        printer_dpi,
+    );
+    let print_scaling = super::print_scaling(options.scale_mode);
 
     let mut last_error = "Failed to print image";
 
@@ -33,9 +36,10 @@ pub fn print_image(
         match print_file_with_optional_media_fallback(
             printer_system_name,
             png_path,
-            print_name,
+            options.name,
             &copies,
             custom_media.as_deref(),
+            print_scaling,
             document_format,
             printer_resolution.as_deref(),
         ) {
@@ -47,17 +51,24 @@ pub fn print_image(
     Err(last_error)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn print_file_with_optional_media_fallback(
     printer_system_name: &str,
     file_path: &str,
     print_name: Option<&str>,
     copies: &str,
     custom_media: Option<&str>,
+    print_scaling: &str,
     document_format: Option<&str>,
     printer_resolution: Option<&str>,
 ) -> Result<u64, &'static str> {
-    let first_options =
-        build_print_options(copies, custom_media, document_format, printer_resolution);
+    let first_options = build_print_options(
+        copies,
+        custom_media,
+        print_scaling,
+        document_format,
+        printer_resolution,
+    );
     let first_try =
         print_file_with_options(printer_system_name, file_path, print_name, &first_options);
 
@@ -65,7 +76,13 @@ fn print_file_with_optional_media_fallback(
         return first_try;
     }
 
-    let fallback_options = build_print_options(copies, None, document_format, printer_resolution);
+    let fallback_options = build_print_options(
+        copies,
+        None,
+        print_scaling,
+        document_format,
+        printer_resolution,
+    );
     print_file_with_options(
         printer_system_name,
         file_path,
@@ -77,6 +94,7 @@ fn print_file_with_optional_media_fallback(
 fn build_print_options(
     copies: &str,
     custom_media: Option<&str>,
+    print_scaling: &str,
     document_format: Option<&str>,
     printer_resolution: Option<&str>,
 ) -> Vec<(String, String)> {
@@ -95,7 +113,7 @@ fn build_print_options(
 
     if let Some(media) = custom_media {
         options.push((String::from("media"), String::from(media)));
-        options.push((String::from("print-scaling"), String::from("fit")));
+        options.push((String::from("print-scaling"), String::from(print_scaling)));
     }
 
     options
@@ -118,8 +136,10 @@ fn print_file_with_options(
         PrinterJobOptions {
             name: print_name,
             raw_properties: &raw_properties,
+            ..PrinterJobOptions::none()
         },
     )
+    .map(|result| result.job_id)
 }
 
 fn query_printer_dpi(printer_system_name: &str) -> Option<(i32, i32)> {
@@ -213,11 +233,7 @@ fn parse_resolution_value(value: &str) -> Option<(i32, i32)> {
     }
 
     let dpi = parse_first_i32(text)?;
-    if dpi > 0 {
-        Some((dpi, dpi))
-    } else {
-        None
-    }
+    if dpi > 0 { Some((dpi, dpi)) } else { None }
 }
 
 fn parse_first_i32(text: &str) -> Option<i32> {
@@ -1,6 +1,12 @@
 use image::{DynamicImage, ImageFormat};
 use std::io::Cursor;
 
+use crate::common::base::{
+    image::{PrintImageOptions, ScaleMode, flatten_alpha},
+    job::PrintResult,
+    media::MediaSize,
+};
+
 #[cfg(target_os = "macos")]
 mod macos;
 
@@ -10,33 +16,33 @@ mod linux;
 pub fn print_image(
     printer_system_name: &str,
     image: DynamicImage,
-    print_name: Option<&str>,
-    page_count: u32,
-    print_width: Option<f64>,
-    print_height: Option<f64>,
+    options: PrintImageOptions,
+) -> Result<PrintResult, &'static str> {
+    let image = flatten_alpha(image, options.background);
+
+    // CUPS prints every copy in a single job, so pages are either all sent or none
+    let job_id = submit_image(printer_system_name, image, options)?;
+
+    Ok(PrintResult {
+        job_id,
+        pages_printed: Some(normalize_page_count(options.page_count)),
+        error: None,
+    })
+}
+
+fn submit_image(
+    printer_system_name: &str,
+    image: DynamicImage,
+    options: PrintImageOptions,
 ) -> Result<u64, &'static str> {
     #[cfg(target_os = "macos")]
     {
-        return macos::print_image(
-            printer_system_name,
-            image,
-            print_name,
-            page_count,
-            print_width,
-            print_height,
-        );
+        return macos::print_image(printer_system_name, image, options);
     }
 
     #[cfg(all(target_family = "unix", not(target_os = "macos")))]
     {
-        return linux::print_image(
-            printer_system_name,
-            image,
-            print_name,
-            page_count,
-            print_width,
-            print_height,
-        );
+        return linux::print_image(printer_system_name, image, options);
     }
 
     #[allow(unreachable_code)]
@@ -54,6 +60,13 @@ fn image_to_png_bytes(image: &DynamicImage) -> Result<Vec<u8>, &'static str> {
     Ok(bytes)
 }
 
+fn print_scaling(scale_mode: ScaleMode) -> &'static str {
+    match scale_mode {
+        ScaleMode::Fit => "fit",
+        ScaleMode::Center | ScaleMode::None => "none",
+    }
+}
+
 fn normalize_page_count(page_count: u32) -> u32 {
     page_count.max(1)
 }
@@ -63,17 +76,11 @@ fn media_custom_mm(width: f64, height: f64) -> Option<String> {
         return None;
     }
 
-    Some(format!(
-        "Custom.{}x{}mm",
-        fmt_mm(width),
-        fmt_mm(height)
-    ))
-}
-
-fn fmt_mm(value: f64) -> String {
-    let formatted = format!("{value:.2}");
-    formatted
-        .trim_end_matches('0')
-        .trim_end_matches('.')
-        .to_string()
+    Some(
+        MediaSize {
+            width_mm: width,
+            height_mm: height,
+        }
+        .to_cups_media(),
+    )
 }
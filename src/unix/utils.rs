@@ -1,3 +1,2 @@
-pub mod date;
 pub mod file;
 pub mod strings;
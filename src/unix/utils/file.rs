@@ -1,7 +1,7 @@
 use std::{
     env,
     fs::File,
-    io::Write,
+    io::{Read, Write},
     path::PathBuf,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -28,9 +28,13 @@ pub fn save_tmp_file_with_ext(buffer: &[u8], extension: &str) -> Option<PathBuf>
     let mut tmp_file = File::create(&file_path).ok()?;
     let save = tmp_file.write_all(buffer).and_then(|_| tmp_file.sync_all());
 
-    if save.is_ok() {
-        Some(file_path)
-    } else {
-        None
+    if save.is_ok() { Some(file_path) } else { None }
+}
+
+pub fn read_file_head(path: &str, len: usize) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(len);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(len as u64).read_to_end(&mut buffer);
     }
+    buffer
 }
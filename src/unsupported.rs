@@ -0,0 +1,209 @@
+use crate::common::base::{
+    image::PrintImageOptions,
+    job::{
+        JobInfoLevel, PrintResult, PrinterJob, PrinterJobCapabilities, PrinterJobOptions,
+        PrinterJobState,
+    },
+    media::{FormInfo, FormSpec},
+    permissions::AccessControlEntry,
+    printer::{PortInfo, Printer, PrinterState},
+};
+use crate::common::traits::platform::{DeviceCaps, FeatureSet, PlatformActions};
+use image::DynamicImage;
+use std::io::Read;
+use std::path::PathBuf;
+
+const UNSUPPORTED: &str = "Printing is not supported on this platform";
+
+/**
+ * Fallback for targets without a printing backend: queries are empty and actions fail
+ */
+impl PlatformActions for crate::Platform {
+    type Session = ();
+
+    fn supported_features() -> FeatureSet {
+        FeatureSet::default()
+    }
+
+    fn get_printers() -> Vec<Printer> {
+        vec![]
+    }
+
+    fn try_get_printers() -> Result<Vec<Printer>, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn printers_change_cookie() -> Option<u64> {
+        None
+    }
+
+    fn get_printer_caps(_printer_system_name: &str) -> DeviceCaps {
+        DeviceCaps {
+            dpi_x: 0,
+            dpi_y: 0,
+            page_width: 0,
+            page_height: 0,
+            print_table_width: 0,
+            print_table_height: 0,
+            margin_top: 0,
+            margin_left: 0,
+            margin_right: 0,
+            margin_bottom: 0,
+        }
+    }
+
+    fn get_job_capabilities(_printer_system_name: &str) -> PrinterJobCapabilities {
+        PrinterJobCapabilities {
+            max_copies: None,
+            duplex: false,
+            collate: false,
+        }
+    }
+
+    fn print(
+        _printer_system_name: &str,
+        _buffer: &[u8],
+        _options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn open_session(_printer_system_name: &str) -> Result<Self::Session, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn print_in_session(
+        _session: &Self::Session,
+        _printer_system_name: &str,
+        _buffer: &[u8],
+        _options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn print_file(
+        _printer_system_name: &str,
+        _file_path: &str,
+        _options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn print_reader(
+        _printer_system_name: &str,
+        _reader: &mut dyn Read,
+        _options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn print_image(
+        _printer_system_name: &str,
+        _buffer: DynamicImage,
+        _options: PrintImageOptions,
+    ) -> Result<PrintResult, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn get_printer_jobs(_printer_name: &str, _active_only: bool) -> Vec<PrinterJob> {
+        vec![]
+    }
+
+    fn get_printer_jobs_level(
+        _printer_name: &str,
+        _active_only: bool,
+        _level: JobInfoLevel,
+    ) -> Vec<PrinterJob> {
+        vec![]
+    }
+
+    fn get_printer_jobs_paged(_printer_name: &str, _offset: u32, _limit: u32) -> Vec<PrinterJob> {
+        vec![]
+    }
+
+    fn get_spool_directory(_printer_name: &str) -> PathBuf {
+        PathBuf::new()
+    }
+
+    fn get_ports() -> Vec<PortInfo> {
+        vec![]
+    }
+
+    fn get_forms(_printer_name: &str) -> Vec<FormInfo> {
+        vec![]
+    }
+
+    fn add_form(_printer_name: &str, _form: &FormSpec) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn delete_form(_printer_name: &str, _form_name: &str) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn get_printer_permissions(
+        _printer_name: &str,
+    ) -> Result<Vec<AccessControlEntry>, &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn get_print_processors() -> Vec<String> {
+        vec![]
+    }
+
+    fn get_computer_name() -> Option<String> {
+        None
+    }
+
+    fn rename_printer(_printer_name: &str, _new_name: &str) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn set_printer_shared(
+        _printer_name: &str,
+        _shared: bool,
+        _share_name: Option<&str>,
+    ) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn get_default_printer() -> Option<Printer> {
+        None
+    }
+
+    fn get_printer_by_name(_printer_name: &str) -> Option<Printer> {
+        None
+    }
+
+    fn get_printer_status(_printer_name: &str) -> Option<(u64, Vec<String>)> {
+        None
+    }
+
+    fn parse_printer_state(_platform_state: u64, _state_reasons: &str) -> PrinterState {
+        PrinterState::UNKNOWN
+    }
+
+    fn parse_printer_job_state(_platform_state: u64) -> PrinterJobState {
+        PrinterJobState::UNKNOWN
+    }
+
+    fn set_job_state(
+        _printer_name: &str,
+        _job_id: u64,
+        _state: PrinterJobState,
+    ) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+
+    fn job_exists(_printer_name: &str, _job_id: u64) -> bool {
+        false
+    }
+
+    fn set_job_priority(
+        _printer_name: &str,
+        _job_id: u64,
+        _priority: u32,
+    ) -> Result<(), &'static str> {
+        Err(UNSUPPORTED)
+    }
+}
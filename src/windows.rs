@@ -1,39 +1,142 @@
-use std::mem;
-use image::DynamicImage;
-use windows::core::{PCWSTR, PWSTR};
-use windows::Win32::Graphics::Gdi::{CreateCompatibleBitmap, CreateCompatibleDC, CreateDCW, DeleteDC, DeleteObject, GetDeviceCaps, SelectObject, SetDIBits, SetStretchBltMode, StretchBlt, BITMAPINFO, BITMAPINFOHEADER, DEVMODEW, DIB_RGB_COLORS, DMPAPER_USER, DM_IN_BUFFER, DM_OUT_BUFFER, DM_PAPERLENGTH, DM_PAPERSIZE, DM_PAPERWIDTH, HALFTONE, HGDIOBJ, HORZRES, LOGPIXELSY, PHYSICALOFFSETX, PHYSICALOFFSETY, RGBQUAD, SRCCOPY, VERTRES};
-use windows::Win32::Graphics::Printing::{ClosePrinter, DocumentPropertiesW, EndDocPrinter, EndPagePrinter, OpenPrinterW, StartDocPrinterW, StartPagePrinter, DOC_INFO_1W, PRINTER_HANDLE};
-use windows::Win32::Storage::Xps::{EndDoc, EndPage, StartDocW, StartPage, DOCINFOW};
-use windows::Win32::UI::WindowsAndMessaging::IDOK;
-use crate::common::base::job::{PrinterJobOptions, PrinterJobState};
-use crate::common::base::printer::PrinterState;
-use crate::common::base::{job::PrinterJob, printer::Printer};
-use crate::common::traits::platform::{DeviceCaps, PlatformActions, PlatformPrinterGetters};
+use crate::common::base::image::{PrintImageOptions, ScaleMode, StretchMode, WHITE, flatten_alpha};
+use crate::common::base::job::DocumentFormat;
+use crate::common::base::job::{
+    JobInfoLevel, PrintResult, PrinterJobCapabilities, PrinterJobOptions, PrinterJobState,
+};
+use crate::common::base::media::{FormInfo, FormSpec, MediaSize};
+use crate::common::base::permissions::AccessControlEntry;
+use crate::common::base::printer::{PRINTER_NOT_FOUND, PrinterState};
+use crate::common::base::{
+    job::PrinterJob,
+    printer::{PortInfo, Printer},
+};
+use crate::common::traits::platform::{
+    DeviceCaps, FeatureSet, PlatformActions, PlatformPrinterGetters,
+};
+use crate::windows::utils::bitmap::image_to_dib;
+use crate::windows::utils::pages::{page_result, print_pages};
 use crate::windows::utils::strings::str_to_wide_string;
+use crate::windows::winspool::handle::PrinterHandle;
 use crate::windows::winspool::info::get_device_caps;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use std::io::Read;
+use std::path::PathBuf;
+use windows::Win32::Foundation::{COLORREF, RECT};
+use windows::Win32::Graphics::Gdi::{
+    BLACKONWHITE, BitBlt, COLORONCOLOR, CreateCompatibleBitmap, CreateCompatibleDC, CreateDCW,
+    CreateSolidBrush, DEVMODEW, DIB_RGB_COLORS, DM_IN_BUFFER, DM_OUT_BUFFER, DM_PAPERLENGTH,
+    DM_PAPERSIZE, DM_PAPERWIDTH, DMPAPER_USER, DeleteDC, DeleteObject, FillRect, HALFTONE, HGDIOBJ,
+    SRCCOPY, SelectObject, SetBrushOrgEx, SetDIBits, SetStretchBltMode, StretchBlt,
+};
+use windows::Win32::Graphics::Printing::{
+    DOC_INFO_1W, DocumentPropertiesW, EndDocPrinter, EndPagePrinter, StartDocPrinterW,
+    StartPagePrinter,
+};
+use windows::Win32::Storage::Xps::{DOCINFOW, EndDoc, EndPage, StartDocW, StartPage};
+use windows::Win32::UI::WindowsAndMessaging::IDOK;
+use windows::core::{PCWSTR, PWSTR};
 
 mod utils;
 mod winspool;
 
+const READ_ERROR: &str = "Failed to read the document";
+const PDF_UNSUPPORTED: &str =
+    "PDF files are not supported on Windows, set document_format to send them as RAW";
+
+// JOB_INFO.Status bits
+const JOB_STATUS_PAUSED: u32 = 0x0001;
+const JOB_STATUS_ERROR: u32 = 0x0002;
+const JOB_STATUS_DELETING: u32 = 0x0004;
+const JOB_STATUS_SPOOLING: u32 = 0x0008;
+const JOB_STATUS_PRINTING: u32 = 0x0010;
+const JOB_STATUS_PRINTED: u32 = 0x0080;
+const JOB_STATUS_DELETED: u32 = 0x0100;
+const JOB_STATUS_RESTART: u32 = 0x0800;
+const JOB_STATUS_COMPLETE: u32 = 0x1000;
+const JOB_STATUS_RETAINED: u32 = 0x2000;
+const JOB_STATUS_RENDERING_LOCALLY: u32 = 0x4000;
+// Offline, paper out, blocked queue and user intervention
+const JOB_STATUS_NEEDS_ATTENTION: u32 = 0x0020 | 0x0040 | 0x0200 | 0x0400;
+
 impl PlatformActions for crate::Platform {
+    type Session = PrinterHandle;
+
+    fn supported_features() -> FeatureSet {
+        FeatureSet {
+            printing: true,
+            image_printing: true,
+            job_control: true,
+            job_priority: true,
+            installation: false,
+        }
+    }
+
     fn get_printers() -> Vec<Printer> {
-         winspool::info::enum_printers(None)
+        winspool::info::enum_printers(None)
+    }
+
+    fn try_get_printers() -> Result<Vec<Printer>, &'static str> {
+        winspool::info::try_enum_printers(None)
+    }
+
+    fn printers_change_cookie() -> Option<u64> {
+        winspool::notify::printers_change_cookie()
     }
 
     fn get_printer_caps(printer_system_name: &str) -> DeviceCaps {
         get_device_caps(printer_system_name)
     }
 
+    fn get_job_capabilities(printer_system_name: &str) -> PrinterJobCapabilities {
+        winspool::info::get_job_capabilities(printer_system_name)
+    }
+
     fn print(
         printer_system_name: &str,
         buffer: &[u8],
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str> {
+    ) -> Result<PrintResult, &'static str> {
+        let (copies, data_type) = job_settings(printer_system_name, buffer, &options)?;
+
         winspool::jobs::print_buffer(
             printer_system_name,
             options.name,
             buffer,
-            options.raw_properties,
+            data_type,
+            copies,
+            options.stream,
+            options.get_raw_property("form"),
+            options.is_held(),
+            options.output_file,
+        )
+    }
+
+    fn open_session(printer_system_name: &str) -> Result<Self::Session, &'static str> {
+        PrinterHandle::open(printer_system_name)
+    }
+
+    fn print_in_session(
+        session: &Self::Session,
+        printer_system_name: &str,
+        buffer: &[u8],
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        // A form needs a handle opened with its device mode, so it cannot reuse the session
+        if options.get_raw_property("form").is_some() {
+            return Self::print(printer_system_name, buffer, options);
+        }
+
+        let (copies, data_type) = job_settings(printer_system_name, buffer, &options)?;
+        winspool::jobs::write_document(
+            session,
+            options.name,
+            buffer,
+            data_type,
+            copies,
+            options.stream,
+            options.is_held(),
+            options.output_file,
         )
     }
 
@@ -41,250 +144,433 @@ impl PlatformActions for crate::Platform {
         printer_system_name: &str,
         file_path: &str,
         options: PrinterJobOptions,
-    ) -> Result<u64, &'static str> {
-        let buffer = utils::file::get_file_as_bytes(file_path);
-        if buffer.is_some() {
-            Self::print(printer_system_name, &buffer.unwrap(), options)
-        } else {
-            Err("failed to read file")
+    ) -> Result<PrintResult, &'static str> {
+        let Some(buffer) = utils::file::get_file_as_bytes(file_path) else {
+            return Err("failed to read file");
+        };
+
+        // There is no PDF renderer, and most RAW printers would print a PDF as text. A format
+        // given explicitly still sends it as RAW, for printers that interpret PDF themselves
+        if options.is_auto_format() && DocumentFormat::detect(&buffer) == Some(DocumentFormat::Pdf)
+        {
+            return Err(PDF_UNSUPPORTED);
+        }
+
+        // RAW printers cannot interpret image files, so they are rendered through GDI
+        #[cfg(feature = "image-files")]
+        if options.is_auto_format()
+            && let Some(DocumentFormat::Png | DocumentFormat::Jpeg) =
+                DocumentFormat::detect(&buffer)
+        {
+            // GDI jobs are started by StartDocW, which returns before they can be paused
+            if options.is_held() {
+                return Err("Held jobs are not supported for images");
+            }
+            let image = image::load_from_memory(&buffer).map_err(|_| "Failed to decode image")?;
+            return Self::print_image(printer_system_name, image, options.image_options());
         }
+
+        Self::print(printer_system_name, &buffer, options)
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        options: PrinterJobOptions,
+    ) -> Result<PrintResult, &'static str> {
+        // The first chunk decides the datatype like a buffer would
+        let mut head = vec![];
+        (&mut *reader)
+            .take(options.stream.chunk_size.max(1) as u64)
+            .read_to_end(&mut head)
+            .map_err(|_| READ_ERROR)?;
+        if head.is_empty() {
+            return options.on_empty.empty_result();
+        }
+
+        let (copies, data_type) = job_settings(printer_system_name, &head, &options)?;
+        // Every copy writes the document again, which needs all of it in memory
+        if copies > 1 {
+            reader.read_to_end(&mut head).map_err(|_| READ_ERROR)?;
+            return Self::print(printer_system_name, &head, options);
+        }
+
+        winspool::jobs::print_stream(
+            printer_system_name,
+            options.name,
+            &head,
+            reader,
+            data_type,
+            options.stream,
+            options.get_raw_property("form"),
+            options.is_held(),
+            options.output_file,
+        )
     }
 
     fn print_image(
         printer_system_name: &str,
         image: DynamicImage,
-        print_name: Option<&str>,
-        page_count: u32,
-        print_width: Option<f64>,
-        print_height: Option<f64>,
-    ) -> Result<u64, &'static str> {
-        let printer_name_wide = str_to_wide_string(printer_system_name);
-        let mut printer_handle = PRINTER_HANDLE::default();
-
-        let result = unsafe {
-            OpenPrinterW(
-                PCWSTR(printer_name_wide.as_ptr()),
-                &mut printer_handle,
-                None
-            )
-        };
+        options: PrintImageOptions,
+    ) -> Result<PrintResult, &'static str> {
+        // Without pages StartDocW/EndDoc would still spool an empty job
+        if options.page_count == 0 {
+            return Err("Page count must be at least 1");
+        }
+        // GDI takes image sizes and coordinates as i32
+        if i32::try_from(image.width()).is_err() || i32::try_from(image.height()).is_err() {
+            return Err("Image is too large");
+        }
 
-        if result.is_err() {
-            return Err("Failed to open printer");
+        // Checked before touching the devmode, the casts would otherwise wrap for long roll media
+        let (paper_width, paper_length) = MediaSize {
+            width_mm: options.print_width.unwrap_or_default(),
+            height_mm: options.print_height.unwrap_or_default(),
         }
+        .to_tenths_mm()?;
 
-        // 将DynamicImage转换为BGRA格式
-        let rgba_image = image.to_rgba8();
-        let (img_width, img_height) = rgba_image.dimensions();
+        let printer_name_wide = str_to_wide_string(printer_system_name);
+        // Closed on every return path when the guard is dropped
+        let printer_guard = PrinterHandle::open(printer_system_name).map_err(|err| {
+            if err == PRINTER_NOT_FOUND {
+                err
+            } else {
+                "Failed to open printer"
+            }
+        })?;
+        let printer_handle = printer_guard.raw();
 
         // 创建设备上下文
         let device = str_to_wide_string("WINSPOOL");
         let hdc = unsafe {
-            if print_height.is_some() || print_width.is_some() {
-                let size_needed = DocumentPropertiesW(None, printer_handle, PCWSTR(printer_name_wide.as_ptr()), None, None, 0);
+            if let Some(form) = options.form {
+                let mut devmode_buffer =
+                    winspool::info::form_devmode(&printer_guard, printer_system_name, form)?;
+                CreateDCW(
+                    PCWSTR(device.as_ptr()),
+                    PCWSTR(printer_name_wide.as_ptr()),
+                    PCWSTR::null(),
+                    Some(devmode_buffer.as_mut_ptr() as *const DEVMODEW),
+                )
+            } else if options.print_height.is_some() || options.print_width.is_some() {
+                let size_needed = DocumentPropertiesW(
+                    None,
+                    printer_handle,
+                    PCWSTR(printer_name_wide.as_ptr()),
+                    None,
+                    None,
+                    0,
+                );
                 if size_needed <= 0 {
                     return Err("Failed to get device mode size");
                 }
-                
+
                 let mut devmode_buffer = vec![0u8; size_needed as usize];
                 let devmode_ptr = devmode_buffer.as_mut_ptr() as *mut DEVMODEW;
-                let result = DocumentPropertiesW(None, printer_handle, PCWSTR(printer_name_wide.as_ptr()), Some(devmode_ptr), None, DM_OUT_BUFFER.0);
+                let result = DocumentPropertiesW(
+                    None,
+                    printer_handle,
+                    PCWSTR(printer_name_wide.as_ptr()),
+                    Some(devmode_ptr),
+                    None,
+                    DM_OUT_BUFFER.0,
+                );
                 if result != IDOK.0 {
                     return Err("Failed to get device mode");
                 }
                 let devmode = &mut *devmode_ptr;
                 devmode.Anonymous1.Anonymous1.dmPaperSize = DMPAPER_USER as i16;
                 devmode.dmFields |= DM_PAPERSIZE;
-                if let Some(height) = print_height {
+                if options.print_height.is_some() {
                     devmode.dmFields |= DM_PAPERLENGTH;
-                    devmode.Anonymous1.Anonymous1.dmPaperLength = (height * 10f64) as i16;
+                    devmode.Anonymous1.Anonymous1.dmPaperLength = paper_length;
                 }
-                if let Some(width) = print_width {
+                if options.print_width.is_some() {
                     devmode.dmFields |= DM_PAPERWIDTH;
-                    devmode.Anonymous1.Anonymous1.dmPaperWidth = (width * 10f64) as i16;
+                    devmode.Anonymous1.Anonymous1.dmPaperWidth = paper_width;
                 }
                 // let result = DocumentPropertiesW(None, printer_handle, PCWSTR(printer_name_wide.as_ptr()), Some(devmode_ptr), None, DM_OUT_BUFFER.0 | DM_IN_BUFFER.0);
                 // if result != IDOK.0 {
                 //     return Err("Failed to get device mode");
                 // }
-                CreateDCW(PCWSTR(device.as_ptr()), PCWSTR(printer_name_wide.as_ptr()), PCWSTR::null(), Some(devmode_ptr))
+                CreateDCW(
+                    PCWSTR(device.as_ptr()),
+                    PCWSTR(printer_name_wide.as_ptr()),
+                    PCWSTR::null(),
+                    Some(devmode_ptr),
+                )
             } else {
-                CreateDCW(PCWSTR(device.as_ptr()), PCWSTR(printer_name_wide.as_ptr()), PCWSTR::null(), None)
+                CreateDCW(
+                    PCWSTR(device.as_ptr()),
+                    PCWSTR(printer_name_wide.as_ptr()),
+                    PCWSTR::null(),
+                    None,
+                )
             }
         };
 
         if hdc.is_invalid() {
-            let _ = unsafe { ClosePrinter(printer_handle) };
+            if winspool::info::is_raw_only(printer_system_name) {
+                return Err(
+                    "Printer is not graphics capable (RAW only), use print to send RAW data",
+                );
+            }
             return Err("Failed to create device context");
         }
 
         // 获取打印机分辨率
-        let width = unsafe { GetDeviceCaps(Some(hdc), HORZRES) };
-        let height = unsafe { GetDeviceCaps(Some(hdc), VERTRES) };
+        // Printer DCs start at the printable area, so the layout only needs its size
+        let (_, _, width, height) = winspool::info::get_dc_caps(hdc).printable_rect();
+        if options
+            .dest_rect
+            .is_some_and(|rect| !rect.is_within(width.max(0) as u32, height.max(0) as u32))
+        {
+            unsafe {
+                let _ = DeleteDC(hdc);
+            }
+            return Err("Destination rect is outside the printable area");
+        }
+
+        // Fit shrinks images larger than the printable area anyway, resampling them
+        // before building the DIB bounds its memory to the page resolution
+        let image = if options.scale_mode == ScaleMode::Fit
+            && width > 0
+            && height > 0
+            && (image.width() > width as u32 || image.height() > height as u32)
+        {
+            image.resize(width as u32, height as u32, FilterType::Triangle)
+        } else {
+            image
+        };
+
+        // 将DynamicImage转换为BGRA格式
+        let dib = image_to_dib(&flatten_alpha(image, options.background));
+        let (img_width, img_height) = (dib.width, dib.height);
 
         // 开始文档
-        let mut doc_name = utils::strings::str_to_wide_string(
-            print_name.unwrap_or("Image Print Job")
-        );
+        let mut doc_name =
+            utils::strings::str_to_document_name(options.name.unwrap_or("Image Print Job"));
+
+        // Drivers that write files, like Microsoft Print to PDF, skip their save dialog
+        // when the output file is given
+        let output_file = options.output_file.map(utils::strings::str_to_wide_string);
 
         let doc_info = DOCINFOW {
             cbSize: 0,
             lpszDocName: PCWSTR(doc_name.as_mut_ptr()),
-            lpszOutput: Default::default(),
+            lpszOutput: output_file
+                .as_ref()
+                .map_or(PCWSTR::null(), |path| PCWSTR(path.as_ptr())),
             lpszDatatype: Default::default(),
             fwType: 0,
         };
-        let job_id = unsafe {
-            StartDocW(hdc, &doc_info)
-        };
+        let job_id = unsafe { StartDocW(hdc, &doc_info) };
         if job_id == 0 {
             unsafe {
                 let _ = DeleteDC(hdc);
-                let _ = ClosePrinter(printer_handle);
             }
             return Err("Failed to start document");
         }
 
-        for _ in 0..page_count {
-            unsafe {
-                let _ = StartPage(hdc);
-            };
-
-            // 创建兼容的内存DC
-            let mem_dc = unsafe { CreateCompatibleDC(Some(hdc)) };
-            if mem_dc.is_invalid() {
-                // 清理资源
-                unsafe {
-                    let _ = EndPage(hdc);
-                    let _ = EndDoc(hdc);
-                    let _ = DeleteDC(hdc);
-                    let _ = ClosePrinter(printer_handle);
-                }
-                return Err("Failed to create compatible DC");
-            }
+        let mut page_error = None;
 
-            // 创建兼容的位图
-            let bitmap = unsafe {
-                CreateCompatibleBitmap(hdc, img_width as i32, img_height as i32)
-            };
+        // The bitmap is the same on every page, so it is only created and filled once
+        // 创建兼容的内存DC
+        let mem_dc = unsafe { CreateCompatibleDC(Some(hdc)) };
+        // 创建兼容的位图
+        let bitmap = if mem_dc.is_invalid() {
+            page_error = Some("Failed to create compatible DC");
+            None
+        } else {
+            let bitmap =
+                unsafe { CreateCompatibleBitmap(hdc, img_width as i32, img_height as i32) };
             if bitmap.is_invalid() {
-                unsafe {
-                    let _ = EndDoc(hdc);
-                    let _ = DeleteDC(mem_dc);
-                    let _ = DeleteDC(hdc);
-                    let _ = ClosePrinter(printer_handle);
-                }
-                return Err("Failed to create compatible bitmap");
+                page_error = Some("Failed to create compatible bitmap");
+                None
+            } else {
+                Some(bitmap)
             }
+        };
 
-            // 选择位图到内存DC
-            let old_bitmap = unsafe { SelectObject(mem_dc, HGDIOBJ::from(bitmap)) };
-
-            // 设置位图信息
-            let bi = BITMAPINFO {
-                bmiHeader: BITMAPINFOHEADER {
-                    biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
-                    biWidth: img_width as i32,
-                    biHeight: -(img_height as i32), // 负值表示顶部到底部的扫描线
-                    biPlanes: 1,
-                    biBitCount: 32,
-                    biCompression: 0, // BI_RGB
-                    biSizeImage: 0,
-                    biXPelsPerMeter: 0,
-                    biYPelsPerMeter: 0,
-                    biClrUsed: 0,
-                    biClrImportant: 0,
-                },
-                bmiColors: [RGBQUAD::default(); 1],
-            };
+        // 选择位图到内存DC
+        let old_bitmap = bitmap
+            .map(|bitmap| unsafe { SelectObject(mem_dc, HGDIOBJ::from(bitmap)) })
+            .filter(|old_bitmap| !old_bitmap.is_invalid());
+        if bitmap.is_some() && old_bitmap.is_none() {
+            page_error = Some("Failed to select the bitmap");
+        }
 
-            // 将图像数据设置到位图中
+        // 将图像数据设置到位图中
+        if let Some(bitmap) = bitmap {
             let dib_result = unsafe {
                 SetDIBits(
                     Some(mem_dc),
                     bitmap,
                     0,
                     img_height,
-                    rgba_image.as_ptr() as *const std::ffi::c_void,
-                    &bi,
+                    dib.pixels.as_ptr() as *const std::ffi::c_void,
+                    dib.info.as_bitmap_info(),
                     DIB_RGB_COLORS,
                 )
             };
-
             if dib_result == 0 {
-                unsafe {
-                    SelectObject(mem_dc, old_bitmap);
-                    let _ = DeleteObject(HGDIOBJ::from(bitmap));
-                    let _ = DeleteDC(mem_dc);
-                    let _ = EndPage(hdc);
-                    let _ = EndDoc(hdc);
-                    let _ = DeleteDC(hdc);
-                    let _ = ClosePrinter(printer_handle);
-                }
-                return Err("Failed to set DIB bits");
+                page_error = Some("Failed to set DIB bits");
             }
+        }
 
-            // 计算居中位置
-            let x_pos = (width - img_width as i32) / 2;
-            let y_pos = 0; // 置顶
-
-            // 设置拉伸模式
-            unsafe { SetStretchBltMode(hdc, HALFTONE) };
-
-            // 绘制图像到打印机DC
-            let stretch_result = unsafe {
-                StretchBlt(
-                    hdc,
-                    x_pos,
-                    y_pos,
-                    img_width as i32,
-                    img_height as i32,
-                    Some(mem_dc),
-                    0,
-                    0,
-                    img_width as i32,
-                    img_height as i32,
-                    SRCCOPY,
-                )
-            };
+        // 计算目标区域
+        let (x_pos, y_pos, dest_width, dest_height) = match options.dest_rect {
+            // Validated against the printable area, which fits in i32
+            Some(rect) => (
+                rect.x as i32,
+                rect.y as i32,
+                rect.width as i32,
+                rect.height as i32,
+            ),
+            None => options
+                .scale_mode
+                .target_rect(img_width, img_height, width, height),
+        };
 
-            if !stretch_result.as_bool() {
-                unsafe {
-                    SelectObject(mem_dc, old_bitmap);
-                    let _ = DeleteObject(HGDIOBJ::from(bitmap));
-                    let _ = DeleteDC(mem_dc);
-                    let _ = EndPage(hdc);
-                    let _ = EndDoc(hdc);
-                    let _ = DeleteDC(hdc);
-                    let _ = ClosePrinter(printer_handle);
+        // 设置拉伸模式
+        let mut stretch_mode = match options.stretch_mode {
+            StretchMode::Halftone => HALFTONE,
+            StretchMode::ColorOnColor => COLORONCOLOR,
+            StretchMode::BlackOnWhite => BLACKONWHITE,
+        };
+
+        // Every page is started and ended exactly once and only counted once the driver
+        // accepted EndPage, so a failed page is neither drawn nor reported as printed
+        let (pages_printed, mut page_error) = match page_error {
+            Some(err) => (0, Some(err)),
+            None => print_pages(options.page_count, || {
+                if unsafe { StartPage(hdc) } <= 0 {
+                    return Err("StartPage failed");
                 }
-                return Err("Failed to stretch blit image");
-            }
-            unsafe {
+
+                // Fill the page area the image does not cover
+                if options.background != WHITE {
+                    let [r, g, b] = options.background;
+                    let page_rect = RECT {
+                        left: 0,
+                        top: 0,
+                        right: width,
+                        bottom: height,
+                    };
+                    unsafe {
+                        let brush = CreateSolidBrush(COLORREF(
+                            r as u32 | (g as u32) << 8 | (b as u32) << 16,
+                        ));
+                        FillRect(hdc, &page_rect, brush);
+                        let _ = DeleteObject(HGDIOBJ::from(brush));
+                    }
+                }
+
+                // Set on every page since drivers may reset the DC attributes in StartPage.
+                // Drivers that reject HALFTONE get COLORONCOLOR for the rest of the job
+                if unsafe { SetStretchBltMode(hdc, stretch_mode) } == 0 && stretch_mode == HALFTONE
+                {
+                    stretch_mode = COLORONCOLOR;
+                    unsafe { SetStretchBltMode(hdc, stretch_mode) };
+                }
+                // HALFTONE requires resetting the brush origin, otherwise output can show banding
+                if stretch_mode == HALFTONE {
+                    let _ = unsafe { SetBrushOrgEx(hdc, 0, 0, None) };
+                }
+
+                // 绘制图像到打印机DC
+                // A 1:1 copy (Center, None or a dest_rect of the image size) needs no resampling
+                let stretch_result =
+                    if dest_width == img_width as i32 && dest_height == img_height as i32 {
+                        unsafe {
+                            BitBlt(
+                                hdc,
+                                x_pos,
+                                y_pos,
+                                dest_width,
+                                dest_height,
+                                Some(mem_dc),
+                                0,
+                                0,
+                                SRCCOPY,
+                            )
+                        }
+                        .is_ok()
+                    } else {
+                        unsafe {
+                            StretchBlt(
+                                hdc,
+                                x_pos,
+                                y_pos,
+                                dest_width,
+                                dest_height,
+                                Some(mem_dc),
+                                0,
+                                0,
+                                img_width as i32,
+                                img_height as i32,
+                                SRCCOPY,
+                            )
+                        }
+                        .as_bool()
+                    };
+
+                let end_page_result = unsafe { EndPage(hdc) };
+
+                if !stretch_result {
+                    return Err("Failed to blit image");
+                }
+                if end_page_result <= 0 {
+                    return Err("EndPage failed");
+                }
+                Ok(())
+            }),
+        };
+
+        unsafe {
+            if let Some(old_bitmap) = old_bitmap {
                 SelectObject(mem_dc, old_bitmap);
+            }
+            if let Some(bitmap) = bitmap {
                 let _ = DeleteObject(HGDIOBJ::from(bitmap));
+            }
+            if !mem_dc.is_invalid() {
                 let _ = DeleteDC(mem_dc);
-                let _ = EndPage(hdc);
             }
         }
 
         // 清理GDI对象
+        let end_doc_result = unsafe { EndDoc(hdc) };
         unsafe {
-            let _ = EndDoc(hdc);
             let _ = DeleteDC(hdc);
-            let _ = ClosePrinter(printer_handle);
+        }
+        if end_doc_result <= 0 && page_error.is_none() {
+            page_error = Some("EndDoc failed");
         }
 
-        Ok(job_id as u64)
+        page_result(job_id as u64, pages_printed, page_error)
     }
 
     fn get_printer_jobs(printer_name: &str, active_only: bool) -> Vec<PrinterJob> {
-        winspool::jobs::enum_printer_jobs(printer_name)
-            .unwrap_or_default()
+        Self::get_printer_jobs_level(printer_name, active_only, JobInfoLevel::Detailed)
+    }
+
+    fn get_printer_jobs_level(
+        printer_name: &str,
+        active_only: bool,
+        level: JobInfoLevel,
+    ) -> Vec<PrinterJob> {
+        let jobs = match level {
+            JobInfoLevel::Basic => winspool::jobs::enum_printer_jobs_basic(printer_name),
+            JobInfoLevel::Detailed => winspool::jobs::enum_printer_jobs(printer_name),
+        };
+
+        jobs.unwrap_or_default()
             .into_iter()
             .filter(|j| {
                 if active_only {
                     j.state == PrinterJobState::PENDING
+                        || j.state == PrinterJobState::SPOOLING
                         || j.state == PrinterJobState::PROCESSING
                         || j.state == PrinterJobState::PAUSED
                 } else {
@@ -294,14 +580,70 @@ impl PlatformActions for crate::Platform {
             .collect()
     }
 
+    fn get_printer_jobs_paged(printer_name: &str, offset: u32, limit: u32) -> Vec<PrinterJob> {
+        winspool::jobs::enum_printer_jobs_range(printer_name, offset, limit).unwrap_or_default()
+    }
+
+    fn get_spool_directory(_printer_name: &str) -> PathBuf {
+        winspool::info::get_spool_directory()
+    }
+
+    fn get_ports() -> Vec<PortInfo> {
+        winspool::info::enum_ports()
+    }
+
+    fn get_forms(printer_name: &str) -> Vec<FormInfo> {
+        winspool::info::enum_forms(printer_name)
+    }
+
+    fn add_form(printer_name: &str, form: &FormSpec) -> Result<(), &'static str> {
+        winspool::info::add_form(printer_name, form)
+    }
+
+    fn delete_form(printer_name: &str, form_name: &str) -> Result<(), &'static str> {
+        winspool::info::delete_form(printer_name, form_name)
+    }
+
+    fn get_printer_permissions(
+        printer_name: &str,
+    ) -> Result<Vec<AccessControlEntry>, &'static str> {
+        winspool::info::get_printer_permissions(printer_name)
+    }
+
+    fn get_print_processors() -> Vec<String> {
+        winspool::info::enum_print_processors()
+    }
+
+    fn get_computer_name() -> Option<String> {
+        utils::system::get_computer_name()
+    }
+
+    fn rename_printer(printer_name: &str, new_name: &str) -> Result<(), &'static str> {
+        winspool::info::rename_printer(printer_name, new_name)
+    }
+
+    fn set_printer_shared(
+        printer_name: &str,
+        shared: bool,
+        share_name: Option<&str>,
+    ) -> Result<(), &'static str> {
+        winspool::info::set_printer_shared(printer_name, shared, share_name)
+    }
+
     fn get_default_printer() -> Option<Printer> {
         winspool::info::get_default_printer()
     }
 
     fn get_printer_by_name(name: &str) -> Option<Printer> {
-        winspool::info::enum_printers(None)
-            .into_iter()
-            .find(|p| p.name == name || p.system_name == name)
+        winspool::info::get_printer(name).or_else(|| {
+            winspool::info::enum_printers(None)
+                .into_iter()
+                .find(|p| p.name == name || p.system_name == name)
+        })
+    }
+
+    fn get_printer_status(printer_name: &str) -> Option<(u64, Vec<String>)> {
+        winspool::info::get_printer_status(printer_name)
     }
 
     fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
@@ -323,13 +665,33 @@ impl PlatformActions for crate::Platform {
     }
 
     fn parse_printer_job_state(platform_state: u64) -> PrinterJobState {
-        match platform_state {
-            1 | 8 => PrinterJobState::PAUSED,
-            4 | 256 => PrinterJobState::CANCELLED,
-            16 | 2048 | 8192 => PrinterJobState::PROCESSING,
-            32 | 64 | 512 | 1024 => PrinterJobState::PENDING,
-            128 | 496 => PrinterJobState::COMPLETED,
-            _ => PrinterJobState::UNKNOWN,
+        // JOB_INFO.Status is a bit set, so bits are tested from the most to the least important
+        let status = platform_state as u32;
+        let has = |bits: u32| status & bits != 0;
+
+        if has(JOB_STATUS_ERROR | JOB_STATUS_NEEDS_ATTENTION) {
+            // Blocked until someone intervenes, the job is still waiting to be printed
+            PrinterJobState::PENDING
+        } else if has(JOB_STATUS_PAUSED) {
+            PrinterJobState::PAUSED
+        } else if has(JOB_STATUS_DELETING | JOB_STATUS_DELETED) {
+            // Printed jobs are also deleted from the queue once done
+            if has(JOB_STATUS_PRINTED) {
+                PrinterJobState::COMPLETED
+            } else {
+                PrinterJobState::CANCELLED
+            }
+        } else if has(JOB_STATUS_PRINTING | JOB_STATUS_RESTART | JOB_STATUS_RENDERING_LOCALLY) {
+            PrinterJobState::PROCESSING
+        } else if has(JOB_STATUS_SPOOLING) {
+            PrinterJobState::SPOOLING
+        } else if has(JOB_STATUS_RETAINED | JOB_STATUS_COMPLETE | JOB_STATUS_PRINTED) {
+            PrinterJobState::COMPLETED
+        } else if status == 0 {
+            // Queued jobs have no status bits until the spooler picks them up
+            PrinterJobState::PENDING
+        } else {
+            PrinterJobState::UNKNOWN
         }
     }
 
@@ -338,12 +700,183 @@ impl PlatformActions for crate::Platform {
         job_id: u64,
         state: PrinterJobState,
     ) -> Result<(), &'static str> {
-        return match state {
-            PrinterJobState::PAUSED => winspool::jobs::set_job_state(printer_name, 1, job_id),
-            PrinterJobState::PENDING => winspool::jobs::set_job_state(printer_name, 4, job_id),
-            PrinterJobState::CANCELLED => winspool::jobs::set_job_state(printer_name, 5, job_id),
-            PrinterJobState::PROCESSING => winspool::jobs::set_job_state(printer_name, 2, job_id),
-            _ => Err("Operation canot be defined"),
+        match state.to_windows_command() {
+            Some(command) => winspool::jobs::set_job_state(printer_name, command, job_id),
+            None => Err("Operation canot be defined"),
+        }
+    }
+
+    fn job_exists(printer_name: &str, job_id: u64) -> bool {
+        winspool::jobs::job_exists(printer_name, job_id)
+    }
+
+    fn set_job_priority(
+        printer_name: &str,
+        job_id: u64,
+        priority: u32,
+    ) -> Result<(), &'static str> {
+        winspool::jobs::set_job_priority(printer_name, job_id, priority)
+    }
+}
+
+/**
+ * Return the validated copies and the winspool datatype of a job
+ */
+fn job_settings<'a>(
+    printer_system_name: &str,
+    buffer: &[u8],
+    options: &PrinterJobOptions<'a>,
+) -> Result<(u32, &'a str), &'static str> {
+    let copies = if options.has_device_options() {
+        crate::Platform::get_job_capabilities(printer_system_name)
+            .validate(options)?
+            .copies
+    } else {
+        1
+    };
+
+    let data_type = match options.document_format {
+        Some(format) => format.resolve(buffer).windows_datatype(),
+        None => options.get_raw_property("document-format").unwrap_or("RAW"),
+    };
+
+    Ok((copies, data_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::base::image::Rect;
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessHandleCount};
+
+    fn state(status: u32) -> PrinterJobState {
+        crate::Platform::parse_printer_job_state(status as u64)
+    }
+
+    fn handle_count() -> u32 {
+        let mut count = 0;
+        unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) }.unwrap();
+        count
+    }
+
+    #[test]
+    fn print_image_failures_close_the_printer() {
+        // An empty dest_rect fails after the printer is opened, the DocumentPropertiesW
+        // device mode is read and the DC is created, without starting a job
+        let Some(printer) = crate::Platform::get_printers().into_iter().next() else {
+            eprintln!("print_image_failures_close_the_printer: skipped, no printers available");
+            return;
+        };
+        let options = PrintImageOptions {
+            print_width: Some(50.0),
+            print_height: Some(50.0),
+            dest_rect: Some(Rect {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            }),
+            ..PrintImageOptions::none()
         };
+        let fail = || {
+            let image = DynamicImage::new_rgb8(4, 4);
+            crate::Platform::print_image(&printer.system_name, image, options)
+        };
+
+        // The first calls may load the driver and keep its handles
+        for _ in 0..5 {
+            assert!(fail().is_err());
+        }
+        let before = handle_count();
+        for _ in 0..200 {
+            assert!(fail().is_err());
+        }
+        let after = handle_count();
+        assert!(
+            after <= before + 10,
+            "{before} handles before, {after} after"
+        );
+
+        for _ in 0..200 {
+            assert!(
+                crate::Platform::print_image(
+                    "printers-test-missing-printer",
+                    DynamicImage::new_rgb8(4, 4),
+                    options
+                )
+                .is_err()
+            );
+        }
+        assert!(handle_count() <= after + 10);
+    }
+
+    #[test]
+    fn parse_job_state_single_bits() {
+        let cases = [
+            (JOB_STATUS_PAUSED, PrinterJobState::PAUSED),
+            (JOB_STATUS_ERROR, PrinterJobState::PENDING),
+            (JOB_STATUS_DELETING, PrinterJobState::CANCELLED),
+            (JOB_STATUS_SPOOLING, PrinterJobState::SPOOLING),
+            (JOB_STATUS_PRINTING, PrinterJobState::PROCESSING),
+            (0x0020, PrinterJobState::PENDING),
+            (0x0040, PrinterJobState::PENDING),
+            (JOB_STATUS_PRINTED, PrinterJobState::COMPLETED),
+            (JOB_STATUS_DELETED, PrinterJobState::CANCELLED),
+            (0x0200, PrinterJobState::PENDING),
+            (0x0400, PrinterJobState::PENDING),
+            (JOB_STATUS_RESTART, PrinterJobState::PROCESSING),
+            (JOB_STATUS_COMPLETE, PrinterJobState::COMPLETED),
+            (JOB_STATUS_RETAINED, PrinterJobState::COMPLETED),
+            (JOB_STATUS_RENDERING_LOCALLY, PrinterJobState::PROCESSING),
+        ];
+
+        for (status, expected) in cases {
+            assert_eq!(state(status), expected, "status {status:#06x}");
+        }
+    }
+
+    #[test]
+    fn parse_job_state_priorities() {
+        let cases = [
+            (0, PrinterJobState::PENDING),
+            (
+                JOB_STATUS_ERROR | JOB_STATUS_PAUSED,
+                PrinterJobState::PENDING,
+            ),
+            (
+                JOB_STATUS_DELETING | JOB_STATUS_PRINTED,
+                PrinterJobState::COMPLETED,
+            ),
+            (
+                JOB_STATUS_PRINTING | JOB_STATUS_SPOOLING,
+                PrinterJobState::PROCESSING,
+            ),
+            (
+                JOB_STATUS_PAUSED | JOB_STATUS_PRINTING,
+                PrinterJobState::PAUSED,
+            ),
+            (
+                JOB_STATUS_DELETED | JOB_STATUS_PRINTING,
+                PrinterJobState::CANCELLED,
+            ),
+        ];
+
+        for (status, expected) in cases {
+            assert_eq!(state(status), expected, "status {status:#06x}");
+        }
+    }
+
+    #[test]
+    fn printed_and_complete_jobs_are_terminal() {
+        // wait_for_job stops polling once the state is terminal
+        for status in [
+            JOB_STATUS_PRINTED,
+            JOB_STATUS_COMPLETE,
+            JOB_STATUS_PRINTED | JOB_STATUS_COMPLETE,
+        ] {
+            let parsed = state(status);
+            assert_eq!(parsed, PrinterJobState::COMPLETED, "status {status:#06x}");
+            assert!(parsed.is_terminal(), "status {status:#06x}");
+        }
     }
 }
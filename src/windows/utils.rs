@@ -1,4 +1,7 @@
-pub mod date;
+pub mod bitmap;
 pub mod file;
 pub mod memory;
+pub mod pages;
+pub mod sizing;
 pub mod strings;
+pub mod system;
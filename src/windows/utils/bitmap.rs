@@ -0,0 +1,141 @@
+use image::{ColorType, DynamicImage};
+use std::mem;
+use windows::Win32::Graphics::Gdi::{BITMAPINFO, BITMAPINFOHEADER, RGBQUAD};
+
+/**
+ * BITMAPINFO with room for a full 8-bit palette
+ */
+#[repr(C)]
+pub struct DibInfo {
+    pub header: BITMAPINFOHEADER,
+    pub colors: [RGBQUAD; 256],
+}
+
+impl DibInfo {
+    pub fn as_bitmap_info(&self) -> &BITMAPINFO {
+        unsafe { &*(self as *const DibInfo as *const BITMAPINFO) }
+    }
+}
+
+/**
+ * Device independent bitmap ready to be passed to SetDIBits
+ */
+pub struct Dib {
+    pub info: Box<DibInfo>,
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/**
+ * Bytes per DIB scan line, which GDI aligns to 4 bytes
+ */
+pub fn dib_stride(width: u32, bit_count: u16) -> usize {
+    (width as usize * bit_count as usize).div_ceil(32) * 4
+}
+
+/**
+ * Convert an image into a top-down DIB, keeping grayscale sources as 8-bit
+ * with a gray palette and converting anything else to 32-bit.
+ * Alpha is ignored, so images should be flattened first
+ */
+pub fn image_to_dib(image: &DynamicImage) -> Dib {
+    let (width, height) = (image.width(), image.height());
+
+    let grayscale = matches!(
+        image.color(),
+        ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16
+    );
+
+    let bit_count: u16 = if grayscale { 8 } else { 32 };
+    let stride = dib_stride(width, bit_count);
+
+    let (colors_used, pixels) = if grayscale {
+        let gray = image.to_luma8();
+        let mut pixels = vec![0u8; stride * height as usize];
+        for (row, line) in gray
+            .as_raw()
+            .chunks_exact(width.max(1) as usize)
+            .enumerate()
+        {
+            pixels[row * stride..row * stride + line.len()].copy_from_slice(line);
+        }
+        (256, pixels)
+    } else {
+        // 32-bit scan lines are always 4 byte aligned, so the buffer needs no padding.
+        // BI_RGB pixels are stored as blue, green, red, reserved
+        let pixels = image
+            .to_rgb8()
+            .pixels()
+            .flat_map(|p| [p.0[2], p.0[1], p.0[0], 0])
+            .collect();
+        (0, pixels)
+    };
+
+    let mut colors = [RGBQUAD::default(); 256];
+    if grayscale {
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = RGBQUAD {
+                rgbBlue: i as u8,
+                rgbGreen: i as u8,
+                rgbRed: i as u8,
+                rgbReserved: 0,
+            };
+        }
+    }
+
+    let info = Box::new(DibInfo {
+        header: BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            biHeight: -(height as i32), // 负值表示顶部到底部的扫描线
+            biPlanes: 1,
+            biBitCount: bit_count,
+            biCompression: 0, // BI_RGB
+            biSizeImage: (stride * height as usize) as u32,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: colors_used,
+            biClrImportant: 0,
+        },
+        colors,
+    });
+
+    Dib {
+        info,
+        pixels,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, RgbImage};
+
+    #[test]
+    fn stride_pads_odd_widths_to_4_bytes() {
+        for (width, stride_8, stride_24, stride_32) in
+            [(1, 4, 4, 4), (3, 4, 12, 12), (5, 8, 16, 20), (7, 8, 24, 28)]
+        {
+            assert_eq!(dib_stride(width, 8), stride_8, "width {width} at 8bpp");
+            assert_eq!(dib_stride(width, 24), stride_24, "width {width} at 24bpp");
+            assert_eq!(dib_stride(width, 32), stride_32, "width {width} at 32bpp");
+        }
+        assert_eq!(dib_stride(0, 32), 0);
+    }
+
+    #[test]
+    fn dib_size_matches_padded_scan_lines() {
+        let gray = image_to_dib(&DynamicImage::ImageLuma8(GrayImage::new(5, 3)));
+        assert_eq!(gray.info.header.biBitCount, 8);
+        assert_eq!(gray.pixels.len(), 8 * 3);
+        assert_eq!(gray.info.header.biSizeImage, 8 * 3);
+
+        let color = image_to_dib(&DynamicImage::ImageRgb8(RgbImage::new(5, 3)));
+        assert_eq!(color.info.header.biBitCount, 32);
+        assert_eq!(color.pixels.len(), 20 * 3);
+        assert_eq!(color.info.header.biSizeImage, 20 * 3);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::common::base::job::PrintResult;
+
+/**
+ * Print `page_count` pages with `print_page`, which starts, draws and ends a single page.
+ * A page is only counted once it ended, and the first page that fails stops the job.
+ * Returns the pages printed and the error that stopped the job
+ */
+pub fn print_pages(
+    page_count: u32,
+    mut print_page: impl FnMut() -> Result<(), &'static str>,
+) -> (u32, Option<&'static str>) {
+    let mut pages_printed = 0;
+    while pages_printed < page_count {
+        if let Err(err) = print_page() {
+            return (pages_printed, Some(err));
+        }
+        pages_printed += 1;
+    }
+    (pages_printed, None)
+}
+
+/**
+ * Result of a job whose pages were counted. A failure after some pages were already sent
+ * still produced a job, so it gives a partial result instead of discarding the job id,
+ * only a job without any page printed is an error
+ */
+pub fn page_result(
+    job_id: u64,
+    pages_printed: u32,
+    error: Option<&'static str>,
+) -> Result<PrintResult, &'static str> {
+    match error {
+        Some(err) if pages_printed == 0 => Err(err),
+        error => Ok(PrintResult {
+            job_id,
+            pages_printed: Some(pages_printed),
+            error,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Run print_pages on a fake DC recording StartPage and EndPage, failing the given page
+     */
+    fn spool(page_count: u32, failing_page: Option<usize>) -> (u32, Option<&'static str>, usize) {
+        let (mut started, mut ended) = (0, 0);
+        let (printed, error) = print_pages(page_count, || {
+            started += 1;
+            if failing_page == Some(started) {
+                return Err("EndPage failed");
+            }
+            ended += 1;
+            Ok(())
+        });
+        assert!(
+            started - ended <= 1,
+            "{started} pages started, {ended} ended"
+        );
+        (printed, error, started)
+    }
+
+    #[test]
+    fn every_page_is_started_once() {
+        // A page started before the loop would show up as an extra blank page
+        for page_count in [1, 2, 5] {
+            assert_eq!(
+                spool(page_count, None),
+                (page_count, None, page_count as usize)
+            );
+        }
+        assert_eq!(spool(0, None), (0, None, 0));
+    }
+
+    #[test]
+    fn failed_page_stops_the_job() {
+        assert_eq!(spool(5, Some(3)), (2, Some("EndPage failed"), 3));
+        assert_eq!(spool(5, Some(1)), (0, Some("EndPage failed"), 1));
+    }
+
+    #[test]
+    fn partial_jobs_keep_their_id() {
+        let partial = page_result(7, 2, Some("EndPage failed")).unwrap();
+        assert_eq!(partial.job_id, 7);
+        assert_eq!(partial.pages_printed, Some(2));
+        assert!(!partial.is_complete());
+
+        let complete = page_result(7, 3, None).unwrap();
+        assert_eq!(complete.pages_printed, Some(3));
+        assert!(complete.is_complete());
+
+        assert_eq!(
+            page_result(7, 0, Some("StartPage failed")),
+            Err("StartPage failed")
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use windows::Win32::Foundation::ERROR_INSUFFICIENT_BUFFER;
+
+/**
+ * Outcome of one call of a Win32 listing API (EnumPrintersW, EnumJobsW)
+ */
+#[derive(Debug)]
+pub enum SizingCall<E> {
+    Done,
+    /**
+     * The buffer was too small (ERROR_INSUFFICIENT_BUFFER), bytes_needed holds the size
+     */
+    InsufficientBuffer(E),
+    Failed(E),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SizingError<E> {
+    Failed(E),
+    /**
+     * Entries were added between every call, so no buffer was ever large enough
+     */
+    KeptChanging,
+}
+
+/**
+ * Classify the result of a listing call
+ */
+pub fn sizing_call(result: windows::core::Result<()>) -> SizingCall<windows::core::Error> {
+    match result {
+        Ok(()) => SizingCall::Done,
+        Err(err) if err.code() == ERROR_INSUFFICIENT_BUFFER.to_hresult() => {
+            SizingCall::InsufficientBuffer(err)
+        }
+        Err(err) => SizingCall::Failed(err),
+    }
+}
+
+/**
+ * Run the two-call protocol of the listing APIs: a first call without buffer gives the size,
+ * the next one fills a buffer of that size. Entries added in between make it fail with
+ * ERROR_INSUFFICIENT_BUFFER again, so it is retried up to `attempts` calls.
+ * `call` receives the buffer, bytes_needed and the entry count like the Win32 function.
+ * Returns the filled buffer and the number of `entry_size` entries it holds,
+ * None when there is nothing to list
+ */
+pub fn call_with_buffer<E>(
+    attempts: usize,
+    entry_size: usize,
+    mut call: impl FnMut(Option<&mut [u8]>, &mut u32, &mut u32) -> SizingCall<E>,
+) -> Result<Option<(Vec<u8>, usize)>, SizingError<E>> {
+    let mut buffer: Vec<u8> = Vec::new();
+
+    for _ in 0..attempts {
+        let mut bytes_needed: u32 = 0;
+        let mut count: u32 = 0;
+
+        let status = call(
+            if buffer.is_empty() {
+                None
+            } else {
+                Some(buffer.as_mut())
+            },
+            &mut bytes_needed,
+            &mut count,
+        );
+
+        match status {
+            // Without a buffer nothing can be returned, a sizing call that succeeds while
+            // reporting a size is called again with a buffer instead of dropping the entries
+            SizingCall::Done if buffer.is_empty() && bytes_needed > 0 => {
+                buffer = vec![0u8; bytes_needed as usize];
+            }
+            SizingCall::Done if buffer.is_empty() || count == 0 => return Ok(None),
+            SizingCall::Done => {
+                // Never read more entries than the buffer holds, whatever the count says
+                let capacity = buffer.len() / entry_size.max(1);
+                return Ok(Some((buffer, (count as usize).min(capacity))));
+            }
+            SizingCall::InsufficientBuffer(_) if bytes_needed > 0 => {
+                buffer = vec![0u8; bytes_needed as usize];
+            }
+            SizingCall::InsufficientBuffer(err) | SizingCall::Failed(err) => {
+                return Err(SizingError::Failed(err));
+            }
+        }
+    }
+
+    Err(SizingError::KeptChanging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY_SIZE: usize = 16;
+
+    /**
+     * Listing call of a queue holding `entries[n]` entries at the n-th call
+     */
+    fn queue(
+        entries: Vec<u32>,
+    ) -> impl FnMut(Option<&mut [u8]>, &mut u32, &mut u32) -> SizingCall<&'static str> {
+        let mut calls = 0;
+        move |buffer, bytes_needed, count| {
+            let len = entries[calls.min(entries.len() - 1)];
+            calls += 1;
+
+            *bytes_needed = len * ENTRY_SIZE as u32;
+            match buffer {
+                Some(buffer) if buffer.len() >= *bytes_needed as usize => {
+                    *count = len;
+                    SizingCall::Done
+                }
+                _ if len == 0 => SizingCall::Done,
+                _ => SizingCall::InsufficientBuffer("ERROR_INSUFFICIENT_BUFFER"),
+            }
+        }
+    }
+
+    fn listed(result: Result<Option<(Vec<u8>, usize)>, SizingError<&str>>) -> Option<usize> {
+        result.unwrap().map(|(_, count)| count)
+    }
+
+    #[test]
+    fn empty_listing_needs_a_single_call() {
+        let mut calls = 0;
+        let result = call_with_buffer(4, ENTRY_SIZE, |buffer, _, _| {
+            calls += 1;
+            assert!(buffer.is_none());
+            SizingCall::<&str>::Done
+        });
+        assert_eq!(listed(result), None);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn sizing_call_then_filled_buffer() {
+        let (buffer, count) = call_with_buffer(4, ENTRY_SIZE, queue(vec![3]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(buffer.len(), 3 * ENTRY_SIZE);
+    }
+
+    #[test]
+    fn sizing_call_reporting_success_and_a_size_is_retried() {
+        let mut calls = 0;
+        let result = call_with_buffer(4, ENTRY_SIZE, |buffer, bytes_needed, count| {
+            calls += 1;
+            *bytes_needed = 2 * ENTRY_SIZE as u32;
+            if buffer.is_some() {
+                *count = 2;
+            }
+            SizingCall::<&str>::Done
+        });
+        assert_eq!(listed(result), Some(2));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let failed = |status: fn() -> SizingCall<&'static str>| {
+            call_with_buffer(4, ENTRY_SIZE, |_, _, _| status())
+        };
+
+        assert_eq!(
+            failed(|| SizingCall::Failed("ERROR_ACCESS_DENIED")),
+            Err(SizingError::Failed("ERROR_ACCESS_DENIED"))
+        );
+        // A buffer too small without a size cannot be grown
+        assert_eq!(
+            failed(|| SizingCall::InsufficientBuffer("ERROR_INSUFFICIENT_BUFFER")),
+            Err(SizingError::Failed("ERROR_INSUFFICIENT_BUFFER"))
+        );
+    }
+
+    #[test]
+    fn count_is_capped_to_the_buffer() {
+        let result = call_with_buffer(4, ENTRY_SIZE, |buffer, bytes_needed, count| {
+            *bytes_needed = 2 * ENTRY_SIZE as u32;
+            match buffer {
+                Some(_) => {
+                    *count = 40;
+                    SizingCall::<&str>::Done
+                }
+                None => SizingCall::InsufficientBuffer("ERROR_INSUFFICIENT_BUFFER"),
+            }
+        });
+        assert_eq!(listed(result), Some(2));
+    }
+
+    #[test]
+    fn growing_listing_is_retried() {
+        assert_eq!(
+            listed(call_with_buffer(4, ENTRY_SIZE, queue(vec![5, 9, 12, 12]))),
+            Some(12)
+        );
+        assert_eq!(
+            call_with_buffer(4, ENTRY_SIZE, queue(vec![5, 9, 12, 20, 30])),
+            Err(SizingError::KeptChanging)
+        );
+    }
+
+    #[test]
+    fn shrinking_listing_reads_the_entries_left() {
+        assert_eq!(
+            listed(call_with_buffer(4, ENTRY_SIZE, queue(vec![50, 20]))),
+            Some(20)
+        );
+        assert_eq!(
+            listed(call_with_buffer(4, ENTRY_SIZE, queue(vec![50, 0]))),
+            None
+        );
+    }
+
+    #[test]
+    fn large_busy_queue() {
+        // A print server queue of 200000 jobs receiving new jobs while it is listed
+        for growth in [1, 100, 5_000] {
+            let entries = vec![200_000, 200_000 + growth, 200_000 + 2 * growth];
+            let (buffer, count) = call_with_buffer(4, ENTRY_SIZE, queue(entries))
+                .unwrap()
+                .unwrap();
+            assert_eq!(count, 200_000 + 2 * growth as usize);
+            assert!(buffer.len() >= count * ENTRY_SIZE);
+        }
+    }
+}
@@ -1,5 +1,10 @@
 use windows::core::PWSTR;
 
+/**
+ * Maximum document name length in UTF-16 units accepted for spooler jobs
+ */
+pub const MAX_DOCUMENT_NAME_LENGTH: usize = 255;
+
 pub fn wchar_t_to_string(value: PWSTR) -> String {
     if value.is_null() {
         return "".to_string();
@@ -16,6 +21,96 @@ pub fn wchar_t_to_string(value: PWSTR) -> String {
     String::from_utf16_lossy(&vec)
 }
 
+/**
+ * Encode a string as NUL terminated UTF-16 for Win32 calls.
+ * Characters outside the BMP are encoded as surrogate pairs, so the length is
+ * value.encode_utf16().count() + 1 and the last element is always 0
+ */
 pub fn str_to_wide_string(value: &str) -> Vec<u16> {
     value.encode_utf16().chain(Some(0)).collect()
 }
+
+/**
+ * Encode a job document name for StartDocPrinterW/StartDocW.
+ * Control characters are removed and names longer than MAX_DOCUMENT_NAME_LENGTH
+ * are truncated with an ellipsis, without splitting surrogate pairs
+ */
+pub fn str_to_document_name(value: &str) -> Vec<u16> {
+    let clean: String = value.chars().filter(|c| !c.is_control()).collect();
+
+    if clean.encode_utf16().count() <= MAX_DOCUMENT_NAME_LENGTH {
+        return str_to_wide_string(&clean);
+    }
+
+    let mut name: Vec<u16> = Vec::with_capacity(MAX_DOCUMENT_NAME_LENGTH + 1);
+    let mut buffer = [0u16; 2];
+    for c in clean.chars() {
+        let encoded = c.encode_utf16(&mut buffer);
+        if name.len() + encoded.len() > MAX_DOCUMENT_NAME_LENGTH - 1 {
+            break;
+        }
+        name.extend_from_slice(encoded);
+    }
+    name.push('…' as u16);
+    name.push(0);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected_length(value: &str) -> usize {
+        let surrogates = value.chars().filter(|c| c.len_utf16() == 2).count();
+        value.chars().count() + surrogates + 1
+    }
+
+    #[test]
+    fn wide_string_is_nul_terminated() {
+        for value in ["", "printer", "漢字プリンタ", "😀", "label 🏷️ 漢字 😀"] {
+            let wide = str_to_wide_string(value);
+            assert_eq!(wide.last(), Some(&0), "{value}");
+            assert_eq!(wide.len(), expected_length(value), "{value}");
+            assert_eq!(String::from_utf16(&wide[..wide.len() - 1]).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn wide_string_encodes_surrogate_pairs() {
+        assert_eq!(str_to_wide_string("😀"), vec![0xD83D, 0xDE00, 0]);
+        assert_eq!(str_to_wide_string("漢"), vec![0x6F22, 0]);
+    }
+
+    #[test]
+    fn document_name_removes_control_characters() {
+        assert_eq!(
+            str_to_document_name("report\r\n\t.pdf"),
+            str_to_wide_string("report.pdf")
+        );
+    }
+
+    #[test]
+    fn document_name_is_truncated_with_ellipsis() {
+        let name = str_to_document_name(&"a".repeat(300));
+        assert_eq!(name.len(), MAX_DOCUMENT_NAME_LENGTH + 1);
+        assert_eq!(name[MAX_DOCUMENT_NAME_LENGTH - 1], '…' as u16);
+        assert_eq!(name.last(), Some(&0));
+
+        let exact = "a".repeat(MAX_DOCUMENT_NAME_LENGTH);
+        assert_eq!(str_to_document_name(&exact), str_to_wide_string(&exact));
+    }
+
+    #[test]
+    fn document_name_keeps_surrogate_pairs_whole() {
+        // The emoji would end one unit past the room left for the ellipsis
+        let value = format!("{}😀tail", "a".repeat(MAX_DOCUMENT_NAME_LENGTH - 2));
+        let name = str_to_document_name(&value);
+        assert_eq!(name.len(), MAX_DOCUMENT_NAME_LENGTH);
+        assert_eq!(name[name.len() - 2], '…' as u16);
+        assert!(String::from_utf16(&name[..name.len() - 1]).is_ok());
+
+        let value = format!("{}😀😀", "a".repeat(MAX_DOCUMENT_NAME_LENGTH - 3));
+        let name = str_to_document_name(&value);
+        assert_eq!(&name[name.len() - 4..], &[0xD83D, 0xDE00, '…' as u16, 0]);
+    }
+}
@@ -0,0 +1,29 @@
+use windows::Win32::System::SystemInformation::{ComputerNameNetBIOS, GetComputerNameExW};
+use windows::core::PWSTR;
+
+/**
+ * Return the NetBIOS name of the local machine, the host part of its share paths
+ */
+pub fn get_computer_name() -> Option<String> {
+    let mut size: u32 = 0;
+    unsafe {
+        // Fails with ERROR_MORE_DATA and sets the size needed, NUL included
+        let _ = GetComputerNameExW(ComputerNameNetBIOS, None, &mut size);
+        if size == 0 {
+            return None;
+        }
+
+        let mut buffer = vec![0u16; size as usize];
+        GetComputerNameExW(
+            ComputerNameNetBIOS,
+            Some(PWSTR(buffer.as_mut_ptr())),
+            &mut size,
+        )
+        .ok()?;
+        // On success the size excludes the NUL
+        buffer.truncate(size as usize);
+        String::from_utf16(&buffer)
+            .ok()
+            .filter(|name| !name.is_empty())
+    }
+}
@@ -1,2 +1,4 @@
+pub mod handle;
 pub mod info;
 pub mod jobs;
+pub mod notify;
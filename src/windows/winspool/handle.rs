@@ -0,0 +1,108 @@
+use crate::common::base::printer::PRINTER_NOT_FOUND;
+use crate::windows::utils::strings::str_to_wide_string;
+use crate::windows::winspool::jobs::open_printer;
+use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_INVALID_PRINTER_NAME};
+use windows::Win32::Graphics::Gdi::DEVMODEW;
+use windows::Win32::Graphics::Printing::{
+    ClosePrinter, OpenPrinterW, PRINTER_ACCESS_RIGHTS, PRINTER_ACCESS_USE, PRINTER_DEFAULTSW,
+    PRINTER_HANDLE,
+};
+use windows::core::{PCWSTR, PWSTR};
+
+/**
+ * Owned printer handle that is closed with ClosePrinter when dropped
+ */
+pub struct PrinterHandle(PRINTER_HANDLE);
+
+impl PrinterHandle {
+    /**
+     * Open a printer by its system name
+     */
+    pub fn open(printer_name: &str) -> Result<Self, &'static str> {
+        Ok(PrinterHandle(PRINTER_HANDLE {
+            Value: open_printer(printer_name)?,
+        }))
+    }
+
+    /**
+     * Open a printer requesting specific access rights, like PRINTER_ALL_ACCESS for administration
+     */
+    pub fn open_with_access(
+        printer_name: &str,
+        access: PRINTER_ACCESS_RIGHTS,
+    ) -> Result<Self, &'static str> {
+        Self::open_with_defaults(
+            printer_name,
+            &PRINTER_DEFAULTSW {
+                pDatatype: PWSTR::null(),
+                pDevMode: std::ptr::null_mut(),
+                DesiredAccess: access,
+            },
+        )
+    }
+
+    /**
+     * Open a printer for printing with a device mode, used as the settings of the jobs
+     * started from this handle
+     */
+    pub fn open_with_devmode(
+        printer_name: &str,
+        devmode: *mut DEVMODEW,
+    ) -> Result<Self, &'static str> {
+        Self::open_with_defaults(
+            printer_name,
+            &PRINTER_DEFAULTSW {
+                pDatatype: PWSTR::null(),
+                pDevMode: devmode,
+                DesiredAccess: PRINTER_ACCESS_USE,
+            },
+        )
+    }
+
+    fn open_with_defaults(
+        printer_name: &str,
+        defaults: &PRINTER_DEFAULTSW,
+    ) -> Result<Self, &'static str> {
+        let printer_name = str_to_wide_string(printer_name);
+        let mut printer_handle = PRINTER_HANDLE::default();
+
+        match unsafe {
+            OpenPrinterW(
+                PCWSTR(printer_name.as_ptr()),
+                &mut printer_handle,
+                Some(defaults),
+            )
+        } {
+            Ok(()) => Ok(PrinterHandle(printer_handle)),
+            Err(err) if err.code() == ERROR_ACCESS_DENIED.to_hresult() => Err("Access denied"),
+            Err(err) if err.code() == ERROR_INVALID_PRINTER_NAME.to_hresult() => {
+                Err(PRINTER_NOT_FOUND)
+            }
+            Err(_) => Err("OpenPrinterW failed"),
+        }
+    }
+
+    /**
+     * Return the raw handle, valid while the guard is alive
+     */
+    pub fn raw(&self) -> PRINTER_HANDLE {
+        self.0
+    }
+}
+
+impl Drop for PrinterHandle {
+    fn drop(&mut self) {
+        let _ = unsafe { ClosePrinter(self.0) };
+    }
+}
+
+/**
+ * Open a printer, run `f` with its handle and close it afterwards, whatever `f` returns
+ */
+pub fn with_printer<T>(
+    printer_name: &str,
+    f: impl FnOnce(&PrinterHandle) -> Result<T, &'static str>,
+) -> Result<T, &'static str> {
+    let printer_guard = PrinterHandle::open(printer_name)?;
+    f(&printer_guard)
+}
@@ -1,19 +1,59 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
-use std::{ptr, slice};
-use windows::core::{PCWSTR, PWSTR};
-use windows::Win32::Graphics::Gdi::{CreateDCW, DeleteDC, GetDeviceCaps, HORZRES, LOGPIXELSX, LOGPIXELSY, PHYSICALHEIGHT, PHYSICALOFFSETX, PHYSICALOFFSETY, PHYSICALWIDTH, VERTRES};
-use windows::Win32::Graphics::Printing::{EnumPrintersW, GetDefaultPrinterW, PRINTER_ENUM_CONNECTIONS, PRINTER_ENUM_LOCAL, PRINTER_INFO_2W};
+use crate::common::base::job::PrinterJobCapabilities;
+use crate::common::base::media::{FormInfo, FormSpec};
+use crate::common::base::permissions::{AccessControlEntry, PrinterRight};
+use crate::common::base::printer::{PortInfo, Printer};
+use crate::common::traits::platform::DeviceCaps;
+use crate::windows::winspool::handle::{PrinterHandle, with_printer};
 use crate::{
     common::traits::platform::PlatformPrinterGetters,
     windows::utils::{
         memory::{alloc_s, dealloc_s},
+        sizing::{SizingError, call_with_buffer, sizing_call},
         strings::{str_to_wide_string, wchar_t_to_string},
     },
 };
-use crate::common::base::printer::Printer;
-use crate::common::traits::platform::DeviceCaps;
+use std::{env, ffi::c_void, path::PathBuf, ptr, slice};
+use windows::Win32::Foundation::{
+    ERROR_ACCESS_DENIED, ERROR_FILE_EXISTS, ERROR_INVALID_FORM_NAME, GetLastError, RECTL,
+    RPC_S_SERVER_UNAVAILABLE, SIZE,
+};
+use windows::Win32::Graphics::Gdi::{
+    CreateDCW, DEVMODEW, DM_FORMNAME, DM_IN_BUFFER, DM_OUT_BUFFER, DM_PAPERLENGTH, DM_PAPERSIZE,
+    DM_PAPERWIDTH, DeleteDC, GetDeviceCaps, HDC, HORZRES, LOGPIXELSX, LOGPIXELSY, PHYSICALHEIGHT,
+    PHYSICALOFFSETX, PHYSICALOFFSETY, PHYSICALWIDTH, VERTRES,
+};
+use windows::Win32::Graphics::Printing::{
+    AddFormW, ClosePrinter, DeleteFormW, DocumentPropertiesW, EnumFormsW, EnumPortsW,
+    EnumPrintProcessorsW, EnumPrintersW, FORM_INFO_1W, GetDefaultPrinterW, GetPrinterDataW,
+    GetPrinterW, OpenPrinterW, PORT_INFO_2W, PRINTER_ACCESS_ADMINISTER, PRINTER_ACCESS_RIGHTS,
+    PRINTER_ACCESS_USE, PRINTER_ALL_ACCESS, PRINTER_ENUM_CONNECTIONS, PRINTER_ENUM_LOCAL,
+    PRINTER_HANDLE, PRINTER_INFO_2W, PRINTER_INFO_3, PRINTER_INFO_6, PRINTPROCESSOR_INFO_1W,
+    SetPrinterW,
+};
+use windows::Win32::Security::{
+    ACCESS_ALLOWED_ACE, ACE_HEADER, ACL, GetAce, GetSecurityDescriptorDacl, LookupAccountSidW,
+    PSECURITY_DESCRIPTOR, PSID, SID_NAME_USE,
+};
+use windows::Win32::Storage::Xps::{DC_COLLATE, DC_COPIES, DC_DUPLEX, DeviceCapabilitiesW};
+use windows::Win32::UI::WindowsAndMessaging::IDOK;
+use windows::core::{BOOL, PCWSTR, PWSTR};
+
+const PRINTER_ATTRIBUTE_SHARED: u32 = 0x00000008;
+const PRINTER_ATTRIBUTE_RAW_ONLY: u32 = 0x00001000;
+// Not part of the enabled windows features
+const READ_CONTROL: u32 = 0x0002_0000;
+const GENERIC_ALL: u32 = 0x1000_0000;
+const JOB_ACCESS_ADMINISTER: u32 = 0x0000_0010;
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+const INHERIT_ONLY_ACE: u8 = 0x08;
+// DOMAIN and account names are limited to 256 characters
+const MAX_ACCOUNT_NAME: u32 = 257;
+// Sizing call plus retries when printers are added while enumerating
+const ENUM_PRINTERS_ATTEMPTS: usize = 4;
 
 impl PlatformPrinterGetters for PRINTER_INFO_2W {
     fn get_name(&self) -> String {
@@ -26,7 +66,7 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
         wchar_t_to_string(self.pDriverName)
     }
     fn get_is_shared(&self) -> bool {
-        (self.Attributes & 0x00000008) == 8
+        (self.Attributes & PRINTER_ATTRIBUTE_SHARED) != 0
     }
     fn get_uri(&self) -> String {
         "".to_string()
@@ -38,39 +78,7 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
         self.Status as u64
     }
     fn get_state_reasons(&self) -> Vec<String> {
-        // NOTE: These reasons are virtual descriptions based on printer status
-        return [
-            (0x00000000, "ready"),
-            (0x00000001, "paused"),
-            (0x00000002, "error"),
-            (0x00000004, "pending_deletion"),
-            (0x00000008, "paper_jam"),
-            (0x00000010, "paper_out"),
-            (0x00000020, "manual_feed"),
-            (0x00000040, "paper_problem"),
-            (0x00000080, "offline"),
-            (0x00000100, "io_active"),
-            (0x00000200, "busy"),
-            (0x00000400, "printing"),
-            (0x00000800, "output_bin_full"),
-            (0x00001000, "not_available"),
-            (0x00002000, "waiting"),
-            (0x00004000, "processing"),
-            (0x00008000, "initializing"),
-            (0x00010000, "warming_up"),
-            (0x00020000, "toner_low"),
-            (0x00040000, "no_toner"),
-            (0x00080000, "page_punt"),
-            (0x00100000, "user_intervention"),
-            (0x00200000, "out_of_memory"),
-            (0x00400000, "door_open"),
-            (0x00800000, "server_unknown"),
-            (0x01000000, "power_save"),
-        ]
-        .iter()
-        .filter(|v| self.Status & v.0 != 0)
-        .map(|v| v.1.to_string())
-        .collect();
+        status_reasons(self.Status)
     }
     fn get_port_name(&self) -> String {
         wchar_t_to_string(self.pPortName)
@@ -84,6 +92,9 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
     fn get_data_type(&self) -> String {
         wchar_t_to_string(self.pDatatype)
     }
+    fn get_share_name(&self) -> String {
+        wchar_t_to_string(self.pShareName)
+    }
 }
 
 //获取打印机的dpi
@@ -91,10 +102,23 @@ pub fn get_device_caps(printer_name: &str) -> DeviceCaps {
     let printer_name_wide = str_to_wide_string(printer_name);
     let device = str_to_wide_string("WINSPOOL");
     let device_name = PCWSTR(printer_name_wide.as_ptr());
-    let port_name = PCWSTR::null(); // 使用默认端口
+    // CreateDCW ignores the port argument, which only exists for 16-bit compatibility.
+    // The driver and its default DEVMODE are resolved from the printer name alone
+    let port_name = PCWSTR::null();
     unsafe {
         let hdc = CreateDCW(PCWSTR(device.as_ptr()), device_name, port_name, None);
-        let dpi_x = GetDeviceCaps(Some(hdc), LOGPIXELSX);  // 水平 DPI
+        let caps = get_dc_caps(hdc);
+        let _ = DeleteDC(hdc);
+        caps
+    }
+}
+
+/**
+ * Read the resolution, page size and margins of a printer device context
+ */
+pub fn get_dc_caps(hdc: HDC) -> DeviceCaps {
+    unsafe {
+        let dpi_x = GetDeviceCaps(Some(hdc), LOGPIXELSX); // 水平 DPI
         let dpi_y = GetDeviceCaps(Some(hdc), LOGPIXELSY);
         let page_width = GetDeviceCaps(Some(hdc), PHYSICALWIDTH);
         let page_height = GetDeviceCaps(Some(hdc), PHYSICALHEIGHT);
@@ -102,31 +126,123 @@ pub fn get_device_caps(printer_name: &str) -> DeviceCaps {
         let print_table_height = GetDeviceCaps(Some(hdc), VERTRES);
         let margin_left = GetDeviceCaps(Some(hdc), PHYSICALOFFSETX);
         let margin_top = GetDeviceCaps(Some(hdc), PHYSICALOFFSETY);
-        let margin_right = page_width - print_table_width - margin_left;
-        let margin_bottom = page_height - print_table_height - margin_top;
-        let _ = DeleteDC(hdc);
-        DeviceCaps {
-            dpi_x,
-            dpi_y,
-            page_width,
-            page_height,
-            print_table_width,
-            print_table_height,
-            margin_top,
-            margin_left,
-            margin_right,
-            margin_bottom,
+        DeviceCaps::from_driver_values(
+            (dpi_x, dpi_y),
+            (page_width, page_height),
+            (print_table_width, print_table_height),
+            (margin_left, margin_top),
+        )
+    }
+}
+
+/**
+ * Returns copies, duplex and collate support using DeviceCapabilitiesW
+ */
+pub fn get_job_capabilities(printer_name: &str) -> PrinterJobCapabilities {
+    let printer_name_wide = str_to_wide_string(printer_name);
+    let device_name = PCWSTR(printer_name_wide.as_ptr());
+    // Unlike CreateDCW, DeviceCapabilitiesW forwards the port to the driver,
+    // and some network drivers answer differently per port
+    let port_name_wide = get_primary_port(printer_name);
+    let port_name = port_name_wide
+        .as_ref()
+        .map_or(PCWSTR::null(), |port| PCWSTR(port.as_ptr()));
+
+    unsafe {
+        let max_copies = DeviceCapabilitiesW(device_name, port_name, DC_COPIES, None, None);
+        let duplex = DeviceCapabilitiesW(device_name, port_name, DC_DUPLEX, None, None);
+        let collate = DeviceCapabilitiesW(device_name, port_name, DC_COLLATE, None, None);
+
+        // A negative value means the driver could not answer, so the driver decides
+        PrinterJobCapabilities {
+            max_copies: if max_copies > 0 {
+                Some(max_copies as u32)
+            } else {
+                None
+            },
+            duplex: duplex != 0,
+            collate: collate != 0,
         }
     }
 }
 
+/**
+ * Describe the bits of a printer status, these reasons are virtual descriptions
+ * since winspool only reports the status flags
+ */
+fn status_reasons(status: u32) -> Vec<String> {
+    [
+        (0x00000000, "ready"),
+        (0x00000001, "paused"),
+        (0x00000002, "error"),
+        (0x00000004, "pending_deletion"),
+        (0x00000008, "paper_jam"),
+        (0x00000010, "paper_out"),
+        (0x00000020, "manual_feed"),
+        (0x00000040, "paper_problem"),
+        (0x00000080, "offline"),
+        (0x00000100, "io_active"),
+        (0x00000200, "busy"),
+        (0x00000400, "printing"),
+        (0x00000800, "output_bin_full"),
+        (0x00001000, "not_available"),
+        (0x00002000, "waiting"),
+        (0x00004000, "processing"),
+        (0x00008000, "initializing"),
+        (0x00010000, "warming_up"),
+        (0x00020000, "toner_low"),
+        (0x00040000, "no_toner"),
+        (0x00080000, "page_punt"),
+        (0x00100000, "user_intervention"),
+        (0x00200000, "out_of_memory"),
+        (0x00400000, "door_open"),
+        (0x00800000, "server_unknown"),
+        (0x01000000, "power_save"),
+    ]
+    .iter()
+    .filter(|v| status & v.0 != 0)
+    .map(|v| v.1.to_string())
+    .collect()
+}
+
+/**
+ * Return the raw status and its reasons of a single printer using GetPrinterW at level 6,
+ * which only carries the status and is much cheaper than a full enumeration
+ */
+pub fn get_printer_status(printer_name: &str) -> Option<(u64, Vec<String>)> {
+    let buffer = get_printer_info(printer_name, 6)?;
+    let printer_info = unsafe { &*(buffer.as_ptr() as *const PRINTER_INFO_6) };
+    Some((
+        printer_info.dwStatus as u64,
+        status_reasons(printer_info.dwStatus),
+    ))
+}
+
+/**
+ * Return the first port of a printer as a wide string, printers pooled on
+ * several ports list them separated by commas in pPortName
+ */
+fn get_primary_port(printer_name: &str) -> Option<Vec<u16>> {
+    let printer = get_printer(printer_name)?;
+    let port = printer.port_name.split(',').next()?.trim();
+    if port.is_empty() {
+        None
+    } else {
+        Some(str_to_wide_string(port))
+    }
+}
+
 /**
  * Returns all available printer using EnumPrintersW
  */
 pub fn enum_printers(name: Option<&str>) -> Vec<Printer> {
-    let mut bytes_needed: u32 = 0;
-    let mut count_printers: u32 = 0;
+    try_enum_printers(name).unwrap_or_default()
+}
 
+/**
+ * Returns all available printer using EnumPrintersW, reporting enumeration failures
+ */
+pub fn try_enum_printers(name: Option<&str>) -> Result<Vec<Printer>, &'static str> {
     // Store wide name in a variable so it lives long enough
     let name_wide: Option<Vec<u16>> = name.map(str_to_wide_string);
     let name_ptr = match &name_wide {
@@ -134,57 +250,710 @@ pub fn enum_printers(name: Option<&str>) -> Vec<Printer> {
         None => ptr::null(),
     };
 
+    // Printers added between two calls make the next one fail again, so it is retried
+    let listing = call_with_buffer(
+        ENUM_PRINTERS_ATTEMPTS,
+        size_of::<PRINTER_INFO_2W>(),
+        |buffer, bytes_needed, count_printers| {
+            sizing_call(unsafe {
+                EnumPrintersW(
+                    PRINTER_ENUM_LOCAL | PRINTER_ENUM_CONNECTIONS,
+                    PCWSTR(name_ptr),
+                    2,
+                    buffer,
+                    bytes_needed,
+                    count_printers,
+                )
+            })
+        },
+    );
+
+    match listing {
+        Ok(None) => Ok(vec![]),
+        Ok(Some((buffer, count_printers))) => {
+            let printers = unsafe {
+                slice::from_raw_parts(buffer.as_ptr() as *const PRINTER_INFO_2W, count_printers)
+            };
+            Ok(printers
+                .iter()
+                .map(|p| Printer::from_platform_printer_getters(p))
+                .collect())
+        }
+        Err(SizingError::Failed(err)) => Err(enum_printers_error(&err)),
+        Err(SizingError::KeptChanging) => {
+            Err("EnumPrintersW failed, the printer list kept changing")
+        }
+    }
+}
+
+fn enum_printers_error(err: &windows::core::Error) -> &'static str {
+    if err.code() == ERROR_ACCESS_DENIED.to_hresult() {
+        "Access denied enumerating printers"
+    } else if err.code() == RPC_S_SERVER_UNAVAILABLE.to_hresult() {
+        "Print spooler unavailable"
+    } else {
+        "EnumPrintersW failed"
+    }
+}
+
+pub fn get_default_printer_name() -> String {
+    let mut name_size: u32 = 0;
+    unsafe {
+        GetDefaultPrinterW(None, &mut name_size);
+        let mut buffer: Vec<u16> = vec![0; name_size as usize];
+        GetDefaultPrinterW(Some(PWSTR(buffer.as_mut_ptr())), &mut name_size);
+        wchar_t_to_string(PWSTR(buffer.as_mut_ptr()))
+    }
+}
+/**
+ * Returns the default printer, reading only its own info when possible
+ */
+pub fn get_default_printer() -> Option<Printer> {
+    let printer_name = get_default_printer_name();
+    get_printer(&printer_name).or_else(|| {
+        enum_printers(None)
+            .into_iter()
+            .find(|p| p.name == printer_name)
+    })
+}
+
+/**
+ * Returns a single printer using GetPrinterW at level 2, without enumerating all printers
+ */
+pub fn get_printer(printer_name: &str) -> Option<Printer> {
+    let buffer = get_printer_info(printer_name, 2)?;
+    let printer_info = unsafe { &*(buffer.as_ptr() as *const PRINTER_INFO_2W) };
+    Some(Printer::from_platform_printer_getters(printer_info))
+}
+
+/**
+ * Retrieve the raw printer info buffer of a specific level with GetPrinterW
+ */
+pub fn get_printer_info(printer_name: &str, level: u32) -> Option<Vec<u8>> {
+    with_printer(printer_name, |printer_guard| {
+        let printer_handle = printer_guard.raw();
+
+        let mut bytes_needed: u32 = 0;
+        unsafe {
+            let _ = GetPrinterW(printer_handle, level, None, &mut bytes_needed);
+
+            let mut buffer = vec![0u8; bytes_needed as usize];
+            let result = if bytes_needed > 0 {
+                GetPrinterW(
+                    printer_handle,
+                    level,
+                    Some(buffer.as_mut()),
+                    &mut bytes_needed,
+                )
+            } else {
+                BOOL::from(false)
+            };
+
+            if result.as_bool() {
+                Ok(buffer)
+            } else {
+                Err("GetPrinterW failed")
+            }
+        }
+    })
+    .ok()
+}
+
+/**
+ * Returns the entries of the printer DACL read with GetPrinterW level 3, merging the
+ * printer and job (inherit only) entries of each account
+ */
+pub fn get_printer_permissions(
+    printer_name: &str,
+) -> Result<Vec<AccessControlEntry>, &'static str> {
+    let printer = PrinterHandle::open_with_access(
+        printer_name,
+        PRINTER_ACCESS_RIGHTS(READ_CONTROL | PRINTER_ACCESS_USE.0),
+    )?;
+
+    let mut bytes_needed: u32 = 0;
+    let _ = unsafe { GetPrinterW(printer.raw(), 3, None, &mut bytes_needed) };
+    if bytes_needed == 0 {
+        return Err("Failed to read the printer security descriptor");
+    }
+    let mut buffer = vec![0u8; bytes_needed as usize];
+    if !unsafe { GetPrinterW(printer.raw(), 3, Some(buffer.as_mut()), &mut bytes_needed) }.as_bool()
+    {
+        return Err("Failed to read the printer security descriptor");
+    }
+    let info = unsafe { &*(buffer.as_ptr() as *const PRINTER_INFO_3) };
+
+    let mut present = BOOL::from(false);
+    let mut defaulted = BOOL::from(false);
+    let mut dacl: *mut ACL = ptr::null_mut();
+    unsafe {
+        GetSecurityDescriptorDacl(
+            info.pSecurityDescriptor,
+            &mut present,
+            &mut dacl,
+            &mut defaulted,
+        )
+    }
+    .map_err(|_| "Failed to read the printer security descriptor")?;
+    // A missing DACL grants everyone full access, an empty one reports no entries
+    if !present.as_bool() || dacl.is_null() {
+        return Ok(vec![AccessControlEntry {
+            principal: "Everyone".to_string(),
+            allowed: true,
+            rights: vec![
+                PrinterRight::Print,
+                PrinterRight::ManagePrinter,
+                PrinterRight::ManageDocuments,
+            ],
+        }]);
+    }
+
+    let mut entries: Vec<AccessControlEntry> = vec![];
+    for index in 0..unsafe { (*dacl).AceCount } as u32 {
+        let mut ace: *mut c_void = ptr::null_mut();
+        if unsafe { GetAce(dacl, index, &mut ace) }.is_err() || ace.is_null() {
+            continue;
+        }
+        let header = unsafe { &*(ace as *const ACE_HEADER) };
+        let allowed = match header.AceType {
+            ACCESS_ALLOWED_ACE_TYPE => true,
+            ACCESS_DENIED_ACE_TYPE => false,
+            // Object and callback entries are not used on printers
+            _ => continue,
+        };
+        // Allowed and denied entries share the ACCESS_ALLOWED_ACE layout
+        let ace = unsafe { &*(ace as *const ACCESS_ALLOWED_ACE) };
+        let sid = PSID(&ace.SidStart as *const u32 as *mut c_void);
+
+        let rights = ace_rights(ace.Mask, header.AceFlags & INHERIT_ONLY_ACE != 0);
+        if rights.is_empty() {
+            continue;
+        }
+
+        let principal = account_name(sid);
+        match entries
+            .iter_mut()
+            .find(|e| e.principal == principal && e.allowed == allowed)
+        {
+            Some(entry) => {
+                for right in rights {
+                    if !entry.rights.contains(&right) {
+                        entry.rights.push(right);
+                    }
+                }
+            }
+            None => entries.push(AccessControlEntry {
+                principal,
+                allowed,
+                rights,
+            }),
+        }
+    }
+
+    Ok(entries)
+}
+
+/**
+ * Map an access mask to printer rights, inherit only entries apply to the jobs
+ */
+fn ace_rights(mask: u32, inherit_only: bool) -> Vec<PrinterRight> {
+    if inherit_only {
+        return if mask & (JOB_ACCESS_ADMINISTER | GENERIC_ALL) != 0 {
+            vec![PrinterRight::ManageDocuments]
+        } else {
+            vec![]
+        };
+    }
+
+    let mut rights = vec![];
+    if mask & (PRINTER_ACCESS_USE.0 | GENERIC_ALL) != 0 {
+        rights.push(PrinterRight::Print);
+    }
+    if mask & (PRINTER_ACCESS_ADMINISTER.0 | GENERIC_ALL) != 0 {
+        rights.push(PrinterRight::ManagePrinter);
+    }
+    if mask & JOB_ACCESS_ADMINISTER != 0 {
+        rights.push(PrinterRight::ManageDocuments);
+    }
+    rights
+}
+
+/**
+ * Resolve a SID to DOMAIN\name with LookupAccountSidW
+ */
+fn account_name(sid: PSID) -> String {
+    let mut name = vec![0u16; MAX_ACCOUNT_NAME as usize];
+    let mut domain = vec![0u16; MAX_ACCOUNT_NAME as usize];
+    let mut name_size = MAX_ACCOUNT_NAME;
+    let mut domain_size = MAX_ACCOUNT_NAME;
+    let mut sid_type = SID_NAME_USE::default();
+
+    let result = unsafe {
+        LookupAccountSidW(
+            PCWSTR::null(),
+            sid,
+            Some(PWSTR(name.as_mut_ptr())),
+            &mut name_size,
+            Some(PWSTR(domain.as_mut_ptr())),
+            &mut domain_size,
+            &mut sid_type,
+        )
+    };
+    if result.is_err() {
+        // Accounts deleted after being granted access can no longer be resolved
+        return "Unknown account".to_string();
+    }
+
+    let name = wchar_t_to_string(PWSTR(name.as_mut_ptr()));
+    let domain = wchar_t_to_string(PWSTR(domain.as_mut_ptr()));
+    if domain.is_empty() {
+        name
+    } else {
+        format!("{domain}\\{name}")
+    }
+}
+
+/**
+ * Returns true when the printer only accepts RAW data and has no GDI (graphics) driver
+ */
+pub fn is_raw_only(printer_name: &str) -> bool {
+    get_printer_info(printer_name, 2).is_some_and(|buffer| {
+        let info = unsafe { &*(buffer.as_ptr() as *const PRINTER_INFO_2W) };
+        info.Attributes & PRINTER_ATTRIBUTE_RAW_ONLY != 0
+    })
+}
+
+/**
+ * Returns the spooler default spool directory using GetPrinterDataW on the local print server
+ */
+pub fn get_spool_directory() -> PathBuf {
+    let fallback = || {
+        PathBuf::from(env::var("SystemRoot").unwrap_or("C:\\Windows".to_string()))
+            .join("System32")
+            .join("spool")
+            .join("PRINTERS")
+    };
+
+    let mut server_handle = PRINTER_HANDLE::default();
+    if unsafe { OpenPrinterW(PCWSTR::null(), &mut server_handle, None) }.is_err() {
+        return fallback();
+    }
+
+    let value_name = str_to_wide_string("DefaultSpoolDirectory");
+    let mut bytes_needed: u32 = 0;
+    let mut buffer = vec![0u8; 2 * 260];
+
     let result = unsafe {
-        EnumPrintersW(
-            PRINTER_ENUM_LOCAL | PRINTER_ENUM_CONNECTIONS,
-            PCWSTR(name_ptr),
-            2,
+        GetPrinterDataW(
+            server_handle,
+            PCWSTR(value_name.as_ptr()),
             None,
+            Some(buffer.as_mut()),
             &mut bytes_needed,
-            &mut count_printers,
         )
     };
+    let _ = unsafe { ClosePrinter(server_handle) };
 
-    if result.is_ok() || bytes_needed == 0 {
+    if result != 0 || bytes_needed < 2 {
+        return fallback();
+    }
+
+    let wide: Vec<u16> = buffer[..bytes_needed as usize]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|c| *c != 0)
+        .collect();
+
+    PathBuf::from(String::from_utf16_lossy(&wide))
+}
+
+/**
+ * Returns the ports of the local spooler using EnumPortsW
+ */
+pub fn enum_ports() -> Vec<PortInfo> {
+    let mut bytes_needed: u32 = 0;
+    let mut count_ports: u32 = 0;
+
+    let _ = unsafe { EnumPortsW(PCWSTR::null(), 2, None, &mut bytes_needed, &mut count_ports) };
+    if bytes_needed == 0 {
         return vec![];
     }
 
     let mut buffer = vec![0u8; bytes_needed as usize];
-
     let result = unsafe {
-        EnumPrintersW(
-            0x00000002 | 0x00000004,
-            PCWSTR(name_ptr),
+        EnumPortsW(
+            PCWSTR::null(),
             2,
             Some(buffer.as_mut()),
             &mut bytes_needed,
-            &mut count_printers,
+            &mut count_ports,
         )
     };
     if result.is_err() {
         return vec![];
     }
 
+    let ports = unsafe {
+        slice::from_raw_parts(buffer.as_ptr() as *const PORT_INFO_2W, count_ports as usize)
+    };
+    ports
+        .iter()
+        .map(|p| PortInfo {
+            name: wchar_t_to_string(p.pPortName),
+            monitor: wchar_t_to_string(p.pMonitorName),
+            description: wchar_t_to_string(p.pDescription),
+            port_type: p.fPortType,
+        })
+        .collect()
+}
 
-    let printers = unsafe {
-        slice::from_raw_parts(buffer.as_ptr() as *const PRINTER_INFO_2W, count_printers as usize)
+/**
+ * Returns the print processor names of the local spooler using EnumPrintProcessorsW
+ */
+pub fn enum_print_processors() -> Vec<String> {
+    let mut bytes_needed: u32 = 0;
+    let mut count_processors: u32 = 0;
+
+    let _ = unsafe {
+        EnumPrintProcessorsW(
+            PCWSTR::null(),
+            PCWSTR::null(),
+            1,
+            None,
+            &mut bytes_needed,
+            &mut count_processors,
+        )
     };
-    printers.iter().map(|p| Printer::from_platform_printer_getters(p)).collect()
+    if bytes_needed == 0 {
+        return vec![];
+    }
+
+    let mut buffer = vec![0u8; bytes_needed as usize];
+    let result = unsafe {
+        EnumPrintProcessorsW(
+            PCWSTR::null(),
+            PCWSTR::null(),
+            1,
+            Some(buffer.as_mut()),
+            &mut bytes_needed,
+            &mut count_processors,
+        )
+    };
+    if result.is_err() {
+        return vec![];
+    }
+
+    let processors = unsafe {
+        slice::from_raw_parts(
+            buffer.as_ptr() as *const PRINTPROCESSOR_INFO_1W,
+            count_processors as usize,
+        )
+    };
+    processors
+        .iter()
+        .map(|p| wchar_t_to_string(p.pName))
+        .collect()
 }
 
-pub fn get_default_printer_name() -> String {
-    let mut name_size: u32 = 0;
+/**
+ * Retrieve the forms of a printer with EnumFormsW, FORM_INFO_1W sizes are in thousandths of a millimeter
+ */
+pub fn enum_forms(printer_name: &str) -> Vec<FormInfo> {
+    let Ok(printer_handle) = PrinterHandle::open(printer_name) else {
+        return vec![];
+    };
+
+    let mut bytes_needed: u32 = 0;
+    let mut count_forms: u32 = 0;
+
+    let _ = unsafe {
+        EnumFormsW(
+            printer_handle.raw(),
+            1,
+            None,
+            &mut bytes_needed,
+            &mut count_forms,
+        )
+    };
+    if bytes_needed == 0 {
+        return vec![];
+    }
+
+    let mut buffer = vec![0u8; bytes_needed as usize];
+    let result = unsafe {
+        EnumFormsW(
+            printer_handle.raw(),
+            1,
+            Some(buffer.as_mut()),
+            &mut bytes_needed,
+            &mut count_forms,
+        )
+    };
+    if result.is_err() {
+        return vec![];
+    }
+
+    let forms = unsafe {
+        slice::from_raw_parts(buffer.as_ptr() as *const FORM_INFO_1W, count_forms as usize)
+    };
+    forms
+        .iter()
+        .map(|f| FormInfo {
+            name: wchar_t_to_string(f.pName),
+            width_mm: f.Size.cx as f64 / 1000.0,
+            height_mm: f.Size.cy as f64 / 1000.0,
+        })
+        .collect()
+}
+
+/**
+ * Return the default device mode of a printer with dmFormName set to one of its forms,
+ * the form name is matched ignoring case
+ */
+pub fn form_devmode(
+    printer_handle: &PrinterHandle,
+    printer_name: &str,
+    form_name: &str,
+) -> Result<Vec<u8>, &'static str> {
+    let form_name = form_name.trim();
+    let Some(form) = enum_forms(printer_name)
+        .into_iter()
+        .find(|f| f.name.eq_ignore_ascii_case(form_name))
+    else {
+        return Err("Form not found");
+    };
+
+    let form_name_wide = str_to_wide_string(&form.name);
+    let printer_name_wide = str_to_wide_string(printer_name);
+    let device_name = PCWSTR(printer_name_wide.as_ptr());
+
     unsafe {
-        GetDefaultPrinterW(None, &mut name_size);
-        let mut buffer: Vec<u16> = vec![0; name_size as usize];
-        GetDefaultPrinterW(Some(PWSTR(buffer.as_mut_ptr())), &mut name_size);
-        wchar_t_to_string(PWSTR(buffer.as_mut_ptr()))
+        let size_needed =
+            DocumentPropertiesW(None, printer_handle.raw(), device_name, None, None, 0);
+        if size_needed <= 0 {
+            return Err("Failed to get device mode size");
+        }
+
+        let mut devmode_buffer = vec![0u8; size_needed as usize];
+        let devmode_ptr = devmode_buffer.as_mut_ptr() as *mut DEVMODEW;
+        let result = DocumentPropertiesW(
+            None,
+            printer_handle.raw(),
+            device_name,
+            Some(devmode_ptr),
+            None,
+            DM_OUT_BUFFER.0,
+        );
+        if result != IDOK.0 {
+            return Err("Failed to get device mode");
+        }
+
+        let devmode = &mut *devmode_ptr;
+        // dmFormName holds at most CCHFORMNAME characters including the terminator
+        if form_name_wide.len() > devmode.dmFormName.len() {
+            return Err("Form name is too long");
+        }
+        devmode.dmFormName = [0; 32];
+        devmode.dmFormName[..form_name_wide.len()].copy_from_slice(&form_name_wide);
+        devmode.dmFields |= DM_FORMNAME;
+        // A paper size left in the device mode would take precedence over the form
+        devmode.dmFields &= !(DM_PAPERSIZE | DM_PAPERLENGTH | DM_PAPERWIDTH);
+
+        // Let the driver merge the form into the rest of its settings
+        let result = DocumentPropertiesW(
+            None,
+            printer_handle.raw(),
+            device_name,
+            Some(devmode_ptr),
+            Some(devmode_ptr),
+            DM_IN_BUFFER.0 | DM_OUT_BUFFER.0,
+        );
+        if result != IDOK.0 {
+            return Err("Failed to set device mode");
+        }
+
+        Ok(devmode_buffer)
     }
 }
+
 /**
- * Returns the default printer filtering all printers
+ * Register a user form with AddFormW, the size and imageable area are given in thousandths of a millimeter
  */
-pub fn get_default_printer() -> Option<Printer> {
-    let printer_name = get_default_printer_name();
-    enum_printers(None).into_iter().find(|p| p.name == printer_name)
+pub fn add_form(printer_name: &str, form: &FormSpec) -> Result<(), &'static str> {
+    let printer_handle = open_form_admin(printer_name)?;
+
+    let to_microns = |mm: f64| (mm * 1000.0).round() as i32;
+    let (width, height) = (to_microns(form.width_mm), to_microns(form.height_mm));
+    let mut name_wide = str_to_wide_string(form.name.trim());
+    let form_info = FORM_INFO_1W {
+        pName: PWSTR(name_wide.as_mut_ptr()),
+        Size: SIZE {
+            cx: width,
+            cy: height,
+        },
+        ImageableArea: RECTL {
+            left: to_microns(form.margins.left_mm),
+            top: to_microns(form.margins.top_mm),
+            right: width - to_microns(form.margins.right_mm),
+            bottom: height - to_microns(form.margins.bottom_mm),
+        },
+        ..Default::default()
+    };
+
+    unsafe {
+        if AddFormW(
+            printer_handle.raw(),
+            1,
+            &form_info as *const FORM_INFO_1W as *const u8,
+        )
+        .as_bool()
+        {
+            return Ok(());
+        }
+        match GetLastError() {
+            ERROR_ACCESS_DENIED => Err(FORM_ADMIN_REQUIRED),
+            ERROR_FILE_EXISTS => Err("Form already exists"),
+            _ => Err("AddFormW failed"),
+        }
+    }
+}
+
+/**
+ * Delete a user form by name with DeleteFormW, built-in forms cannot be deleted
+ */
+pub fn delete_form(printer_name: &str, form_name: &str) -> Result<(), &'static str> {
+    let printer_handle = open_form_admin(printer_name)?;
+    let form_name_wide = str_to_wide_string(form_name);
+
+    unsafe {
+        if DeleteFormW(printer_handle.raw(), PCWSTR(form_name_wide.as_ptr())).as_bool() {
+            return Ok(());
+        }
+        match GetLastError() {
+            ERROR_ACCESS_DENIED => Err(FORM_ADMIN_REQUIRED),
+            ERROR_INVALID_FORM_NAME => Err("Form not found"),
+            _ => Err("DeleteFormW failed"),
+        }
+    }
+}
+
+const FORM_ADMIN_REQUIRED: &str = "Managing forms requires administrator privileges";
+
+fn open_form_admin(printer_name: &str) -> Result<PrinterHandle, &'static str> {
+    PrinterHandle::open_with_access(printer_name, PRINTER_ACCESS_ADMINISTER).map_err(|err| {
+        if err == "Access denied" {
+            FORM_ADMIN_REQUIRED
+        } else {
+            err
+        }
+    })
+}
+
+/**
+ * Rename a printer updating PRINTER_INFO_2W.pPrinterName with SetPrinterW
+ */
+pub fn rename_printer(printer_name: &str, new_name: &str) -> Result<(), &'static str> {
+    let printer_handle = PrinterHandle::open_with_access(printer_name, PRINTER_ALL_ACCESS)?;
+
+    let mut bytes_needed: u32 = 0;
+    unsafe {
+        let _ = GetPrinterW(printer_handle.raw(), 2, None, &mut bytes_needed);
+        if bytes_needed == 0 {
+            return Err("GetPrinterW failed");
+        }
+
+        let mut buffer = vec![0u8; bytes_needed as usize];
+        if !GetPrinterW(
+            printer_handle.raw(),
+            2,
+            Some(buffer.as_mut()),
+            &mut bytes_needed,
+        )
+        .as_bool()
+        {
+            return Err("GetPrinterW failed");
+        }
+
+        let mut new_name_wide = str_to_wide_string(new_name);
+        let printer_info = &mut *(buffer.as_mut_ptr() as *mut PRINTER_INFO_2W);
+        printer_info.pPrinterName = PWSTR(new_name_wide.as_mut_ptr());
+        // Leave the security descriptor untouched, writing it back requires WRITE_DAC
+        printer_info.pSecurityDescriptor = PSECURITY_DESCRIPTOR::default();
+
+        if SetPrinterW(printer_handle.raw(), 2, Some(buffer.as_ptr()), 0).as_bool() {
+            Ok(())
+        } else if GetLastError() == ERROR_ACCESS_DENIED {
+            Err("Access denied")
+        } else {
+            Err("SetPrinterW failed")
+        }
+    }
+}
+
+const SHARING_ADMIN_REQUIRED: &str = "Sharing printers requires administrator privileges";
+
+/**
+ * Share or unshare a printer by flipping PRINTER_ATTRIBUTE_SHARED of its PRINTER_INFO_2W
+ * with SetPrinterW, which needs PRINTER_ALL_ACCESS (administrator rights)
+ */
+pub fn set_printer_shared(
+    printer_name: &str,
+    shared: bool,
+    share_name: Option<&str>,
+) -> Result<(), &'static str> {
+    let printer_handle = PrinterHandle::open_with_access(printer_name, PRINTER_ALL_ACCESS)
+        .map_err(|err| {
+            if err == "Access denied" {
+                SHARING_ADMIN_REQUIRED
+            } else {
+                err
+            }
+        })?;
+
+    let mut bytes_needed: u32 = 0;
+    unsafe {
+        let _ = GetPrinterW(printer_handle.raw(), 2, None, &mut bytes_needed);
+        if bytes_needed == 0 {
+            return Err("GetPrinterW failed");
+        }
+
+        let mut buffer = vec![0u8; bytes_needed as usize];
+        if !GetPrinterW(
+            printer_handle.raw(),
+            2,
+            Some(buffer.as_mut()),
+            &mut bytes_needed,
+        )
+        .as_bool()
+        {
+            return Err("GetPrinterW failed");
+        }
+
+        let printer_info = &mut *(buffer.as_mut_ptr() as *mut PRINTER_INFO_2W);
+        // A shared printer needs a share name, keep the current one unless a new one is given
+        let current_share_name = wchar_t_to_string(printer_info.pShareName);
+        let share_name = match share_name {
+            Some(name) => name.trim(),
+            None if !current_share_name.is_empty() => current_share_name.as_str(),
+            None => printer_name,
+        };
+        let mut share_name_wide = str_to_wide_string(share_name);
+
+        if shared {
+            printer_info.Attributes |= PRINTER_ATTRIBUTE_SHARED;
+            printer_info.pShareName = PWSTR(share_name_wide.as_mut_ptr());
+        } else {
+            printer_info.Attributes &= !PRINTER_ATTRIBUTE_SHARED;
+        }
+        // Leave the security descriptor untouched, writing it back requires WRITE_DAC
+        printer_info.pSecurityDescriptor = PSECURITY_DESCRIPTOR::default();
+
+        if SetPrinterW(printer_handle.raw(), 2, Some(buffer.as_ptr()), 0).as_bool() {
+            Ok(())
+        } else if GetLastError() == ERROR_ACCESS_DENIED {
+            Err(SHARING_ADMIN_REQUIRED)
+        } else {
+            Err("SetPrinterW failed")
+        }
+    }
 }
@@ -1,19 +1,85 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
-use libc::{ c_ulong, c_void};
-use std::{slice};
-use windows::core::{BOOL, PCWSTR, PWSTR};
-use windows::Win32::Graphics::Printing::*;
+use crate::common::base::job::{PrintResult, PrinterJob, StreamOptions, default_job_name};
+use crate::common::base::printer::PRINTER_NOT_FOUND;
+use crate::windows::winspool::handle::{PrinterHandle, with_printer};
+use crate::windows::winspool::info::form_devmode;
 use crate::{
-    common::traits::platform::PlatformPrinterJobGetters,
-    windows::utils::{
-        date::{calculate_system_time, get_current_epoch},
-        strings::{str_to_wide_string, wchar_t_to_string},
-    },
+    common::{time::system_time_from_utc, traits::platform::PlatformPrinterJobGetters},
+    windows::utils::sizing::{SizingError, call_with_buffer, sizing_call},
+    windows::utils::strings::{str_to_document_name, str_to_wide_string, wchar_t_to_string},
 };
-use crate::common::base::job::PrinterJob;
+use libc::{c_ulong, c_void};
+use std::io::{ErrorKind, Read};
+use std::slice;
+use windows::Win32::Foundation::ERROR_INVALID_PRINTER_NAME;
+use windows::Win32::Graphics::Gdi::DEVMODEW;
+use windows::Win32::Graphics::Printing::*;
+use windows::core::{BOOL, PCWSTR, PWSTR};
+
+const JOB_MIN_PRIORITY: u32 = 1;
+const JOB_MAX_PRIORITY: u32 = 99;
+// Sizing call plus retries when jobs are submitted while enumerating
+const ENUM_JOBS_ATTEMPTS: usize = 4;
+
+impl PlatformPrinterJobGetters for JOB_INFO_2W {
+    fn get_id(&self) -> u64 {
+        self.JobId.into()
+    }
+
+    fn get_name(&self) -> String {
+        wchar_t_to_string(self.pDocument)
+    }
+
+    fn get_state(&self) -> u64 {
+        self.Status.into()
+    }
+
+    fn get_printer(&self) -> String {
+        wchar_t_to_string(self.pPrinterName)
+    }
+
+    fn get_user(&self) -> String {
+        wchar_t_to_string(self.pUserName)
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.Priority
+    }
+
+    fn get_size(&self) -> u64 {
+        self.Size.into()
+    }
+
+    fn get_media_type(&self) -> String {
+        wchar_t_to_string(self.pDatatype)
+    }
+
+    fn get_created_at(&self) -> std::time::SystemTime {
+        system_time_from_utc(
+            self.Submitted.wYear,
+            self.Submitted.wMonth,
+            self.Submitted.wDay,
+            self.Submitted.wHour,
+            self.Submitted.wMinute,
+            self.Submitted.wSecond,
+            self.Submitted.wMilliseconds,
+        )
+    }
 
+    // The spooler keeps no processing nor completion time, StartTime and UntilTime
+    // are the hours a job may print in, not when it did
+    fn get_processed_at(&self) -> Option<std::time::SystemTime> {
+        None
+    }
+
+    fn get_completed_at(&self) -> Option<std::time::SystemTime> {
+        None
+    }
+}
+
+// Level 1 has no size nor start time, the getters report none
 impl PlatformPrinterJobGetters for JOB_INFO_1W {
     fn get_id(&self) -> u64 {
         self.JobId.into()
@@ -31,12 +97,24 @@ impl PlatformPrinterJobGetters for JOB_INFO_1W {
         wchar_t_to_string(self.pPrinterName)
     }
 
+    fn get_user(&self) -> String {
+        wchar_t_to_string(self.pUserName)
+    }
+
+    fn get_priority(&self) -> u32 {
+        self.Priority
+    }
+
+    fn get_size(&self) -> u64 {
+        0
+    }
+
     fn get_media_type(&self) -> String {
         wchar_t_to_string(self.pDatatype)
     }
 
     fn get_created_at(&self) -> std::time::SystemTime {
-        calculate_system_time(
+        system_time_from_utc(
             self.Submitted.wYear,
             self.Submitted.wMonth,
             self.Submitted.wDay,
@@ -48,99 +126,270 @@ impl PlatformPrinterJobGetters for JOB_INFO_1W {
     }
 
     fn get_processed_at(&self) -> Option<std::time::SystemTime> {
-        Some(self.get_created_at())
+        None
     }
 
     fn get_completed_at(&self) -> Option<std::time::SystemTime> {
-        Some(self.get_created_at())
+        None
     }
 }
 
 /**
  * Open printer utility
  */
-fn open_printer(printer_name: &str) -> Result<*mut c_void, &'static str> {
+pub fn open_printer(printer_name: &str) -> Result<*mut c_void, &'static str> {
     let printer_name = str_to_wide_string(printer_name);
     let mut printer_handle = PRINTER_HANDLE::default();
 
-    match unsafe {
-        OpenPrinterW(
-            PCWSTR(printer_name.as_ptr()),
-            &mut printer_handle,
-            None
-        )
-    } {
-        Ok(()) => {
-            Ok(printer_handle.Value)
-        }
-        Err(_) => {
-            Err("OpenPrinterW failed")
-        }
+    match unsafe { OpenPrinterW(PCWSTR(printer_name.as_ptr()), &mut printer_handle, None) } {
+        Ok(()) => Ok(printer_handle.Value),
+        Err(err) if err.code() == ERROR_INVALID_PRINTER_NAME.to_hresult() => Err(PRINTER_NOT_FOUND),
+        Err(_) => Err("OpenPrinterW failed"),
     }
 }
 
 /**
  * Print a buffer as RAW datatype with winspool WritePrinterx
  */
+#[allow(clippy::too_many_arguments)]
 pub fn print_buffer(
     printer_name: &str,
     job_name: Option<&str>,
     buffer: &[u8],
-    options: &[(&str, &str)],
-) -> Result<u64, &'static str> {
-    unsafe {
-        let printer_handle = open_printer(printer_name);
-        if let Err(err) = printer_handle {
-            return Err(err);
+    data_type: &str,
+    copies: u32,
+    stream: StreamOptions,
+    form: Option<&str>,
+    hold: bool,
+    output_file: Option<&str>,
+) -> Result<PrintResult, &'static str> {
+    with_job_printer(printer_name, form, |printer_guard| {
+        write_document(
+            printer_guard,
+            job_name,
+            buffer,
+            data_type,
+            copies,
+            stream,
+            hold,
+            output_file,
+        )
+    })
+}
+
+/**
+ * Print the data read from `reader` after `head` as a single copy, without holding the
+ * whole document in memory. A read failure deletes the partial job
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn print_stream(
+    printer_name: &str,
+    job_name: Option<&str>,
+    head: &[u8],
+    reader: &mut dyn Read,
+    data_type: &str,
+    stream: StreamOptions,
+    form: Option<&str>,
+    hold: bool,
+    output_file: Option<&str>,
+) -> Result<PrintResult, &'static str> {
+    with_job_printer(printer_name, form, |printer_guard| {
+        let printer_handle = printer_guard.raw();
+
+        unsafe {
+            let job_id = start_document(printer_handle, job_name, data_type, hold, output_file)?;
+            if StartPagePrinter(printer_handle) == BOOL::from(false) {
+                let _ = AbortPrinter(printer_handle);
+                return Err("StartPagePrinter failed");
+            }
+
+            write_chunks(printer_handle, head, stream)?;
+            let mut chunk = vec![0u8; stream.chunk_size.max(1)];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => write_chunks(printer_handle, &chunk[..read], stream)?,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => {
+                        let _ = AbortPrinter(printer_handle);
+                        return Err("Failed to read the document");
+                    }
+                }
+            }
+
+            let _ = EndPagePrinter(printer_handle);
+            if EndDocPrinter(printer_handle) == BOOL::from(false) {
+                return Err("EndDocPrinter failed");
+            }
+
+            Ok(PrintResult {
+                job_id: job_id as u64,
+                pages_printed: Some(1),
+                error: None,
+            })
         }
-        let printer_handle = PRINTER_HANDLE {
-            Value: printer_handle.unwrap()
-        };
+    })
+}
 
-        let mut copies = 1;
-        let mut data_type = "RAW";
+/**
+ * Run `f` with the handle a job is started from, opened through with_printer. The device
+ * mode of a job is bound when its handle is opened, so a form needs a second handle opened
+ * with the form device mode, built from the first one
+ */
+fn with_job_printer<T>(
+    printer_name: &str,
+    form: Option<&str>,
+    f: impl FnOnce(&PrinterHandle) -> Result<T, &'static str>,
+) -> Result<T, &'static str> {
+    with_printer(printer_name, |printer_guard| match form {
+        Some(form) => {
+            let mut devmode = form_devmode(printer_guard, printer_name, form)?;
+            let form_guard = PrinterHandle::open_with_devmode(
+                printer_name,
+                devmode.as_mut_ptr() as *mut DEVMODEW,
+            )?;
+            f(&form_guard)
+        }
+        None => f(printer_guard),
+    })
+}
 
-        for option in options {
-            match option.0 {
-                "copies" => copies = option.1.parse().unwrap_or(copies),
-                "document-format" => data_type = option.1,
-                _ => {}
+/**
+ * Send data with WritePrinter in chunks of stream.chunk_size, until every byte is written.
+ * The job is aborted when the spooler stops accepting data, so no truncated document prints
+ */
+fn write_chunks(
+    printer_handle: PRINTER_HANDLE,
+    data: &[u8],
+    stream: StreamOptions,
+) -> Result<(), &'static str> {
+    for chunk in data.chunks(stream.chunk_size.max(1)) {
+        let mut remaining = chunk;
+        while !remaining.is_empty() {
+            let mut bytes_written: c_ulong = 0;
+            let written = unsafe {
+                WritePrinter(
+                    printer_handle,
+                    remaining.as_ptr() as *mut c_void,
+                    remaining.len() as c_ulong,
+                    &mut bytes_written,
+                )
+            };
+            // A successful call that wrote nothing would loop forever
+            if written == BOOL::from(false) || bytes_written == 0 {
+                let _ = unsafe { AbortPrinter(printer_handle) };
+                return Err("WritePrinter failed, the job was aborted");
             }
+            remaining = &remaining[(bytes_written as usize).min(remaining.len())..];
         }
 
+        if stream.flush_each_chunk {
+            let mut bytes_flushed: c_ulong = 0;
+            let _ = unsafe { FlushPrinter(printer_handle, None, 0, &mut bytes_flushed, 0) };
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Start a document on a printer handle and return its job id. A held job is paused
+ * right away, before any data reaches the spooler, so it waits for a resume.
+ * With an output file the spooler writes the job there instead of the printer port
+ */
+unsafe fn start_document(
+    printer_handle: PRINTER_HANDLE,
+    job_name: Option<&str>,
+    data_type: &str,
+    hold: bool,
+    output_file: Option<&str>,
+) -> Result<u32, &'static str> {
+    unsafe {
         let mut pDatatype = str_to_wide_string(data_type);
-        let mut pDocName =
-            str_to_wide_string(job_name.unwrap_or(get_current_epoch().to_string().as_str()));
+        let mut pDocName = str_to_document_name(job_name.unwrap_or(default_job_name().as_str()));
+        let mut pOutputFile = output_file.map(str_to_wide_string);
 
         let doc_info = DOC_INFO_1W {
             pDocName: PWSTR(pDocName.as_mut_ptr()),
             pDatatype: PWSTR(pDatatype.as_mut_ptr()),
-            pOutputFile: PWSTR::null(),
+            pOutputFile: pOutputFile
+                .as_mut()
+                .map_or(PWSTR::null(), |file| PWSTR(file.as_mut_ptr())),
         };
 
         let job_id = StartDocPrinterW(printer_handle, 1, &doc_info);
         if job_id == 0 {
-            let _ = ClosePrinter(printer_handle);
             return Err("StartDocPrinterW failed");
         }
 
-        for _ in 0..copies {
-            if StartPagePrinter(printer_handle) != BOOL::from(false) {
-                let mut bytes_written: c_ulong = 0;
-                let _ = WritePrinter(
-                    printer_handle,
-                    buffer.as_ptr() as *mut c_void,
-                    buffer.len() as c_ulong,
-                    &mut bytes_written,
-                );
-                let _ = EndPagePrinter(printer_handle);
+        if hold && SetJobW(printer_handle, job_id, 0, None, JOB_CONTROL_PAUSE) == BOOL::from(false)
+        {
+            // A job that cannot be held must not print unapproved
+            let _ = AbortPrinter(printer_handle);
+            return Err("Failed to hold the job");
+        }
+
+        Ok(job_id)
+    }
+}
+
+/**
+ * Write a buffer as one document on an open printer handle, which stays open for
+ * further documents, one spooler page per copy. A copy that fails after others were
+ * spooled ends the document with a partial result, whose pages_printed is the zero-based
+ * index of the failed copy. When no copy could start the empty job is aborted
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn write_document(
+    printer_guard: &PrinterHandle,
+    job_name: Option<&str>,
+    buffer: &[u8],
+    data_type: &str,
+    copies: u32,
+    stream: StreamOptions,
+    hold: bool,
+    output_file: Option<&str>,
+) -> Result<PrintResult, &'static str> {
+    // StartDocPrinterW accepts an empty document, which some printers turn into a blank page
+    if buffer.is_empty() {
+        return Err("Nothing to print, the document is empty");
+    }
+
+    let printer_handle = printer_guard.raw();
+
+    unsafe {
+        let job_id = start_document(printer_handle, job_name, data_type, hold, output_file)?;
+
+        // A copy whose page cannot start stops the loop, the copies already written
+        // stay in the job and their count tells the caller which copy failed
+        let mut copies_spooled = 0;
+        while copies_spooled < copies {
+            if StartPagePrinter(printer_handle) == BOOL::from(false) {
+                break;
             }
+            write_chunks(printer_handle, buffer, stream)?;
+            let _ = EndPagePrinter(printer_handle);
+            copies_spooled += 1;
         }
 
-        let _ = EndDocPrinter(printer_handle);
-        let _ = ClosePrinter(printer_handle);
+        // Ending a document without pages would leave an empty job in the spooler
+        if copies_spooled == 0 && copies > 0 {
+            let _ = AbortPrinter(printer_handle);
+            return Err("StartPagePrinter failed, the job was aborted");
+        }
+
+        // Only close once the spooler confirmed the end of the document,
+        // otherwise the tail of the job can be lost on some drivers
+        if EndDocPrinter(printer_handle) == BOOL::from(false) {
+            return Err("EndDocPrinter failed");
+        }
 
-        Ok(job_id as u64)
+        Ok(PrintResult {
+            job_id: job_id as u64,
+            pages_printed: Some(copies_spooled),
+            error: (copies_spooled < copies)
+                .then_some("StartPagePrinter failed, not every copy was spooled"),
+        })
     }
 }
 
@@ -148,87 +397,215 @@ pub fn print_buffer(
  * Retrieve print jobs of a specific printer with EnumJobsW
  */
 pub fn enum_printer_jobs(printer_name: &str) -> Result<Vec<PrinterJob>, &'static str> {
-    let printer_handle = open_printer(printer_name)?;
-    let printer_handle = PRINTER_HANDLE {
-        Value: printer_handle
-    };
+    enum_printer_jobs_range(printer_name, 0, 0xFFFFFFFF)
+}
 
-    let mut bytes_needed: u32 = 0;
-    let mut jobs_count: u32 = 0;
+/**
+ * Retrieve print jobs of a specific printer with EnumJobsW level 1, which is cheaper
+ * to fill for the spooler but has no size, start time nor chained job
+ */
+pub fn enum_printer_jobs_basic(printer_name: &str) -> Result<Vec<PrinterJob>, &'static str> {
+    with_printer(printer_name, |printer_handle| {
+        let Some((buffer, jobs_count)) =
+            enum_jobs_buffer::<JOB_INFO_1W>(printer_handle, 0, 0xFFFFFFFF, 1)?
+        else {
+            return Ok(vec![]);
+        };
 
-    // First call to determine the required buffer size
-    let first_call_result = unsafe {
-        EnumJobsW(
-            printer_handle,
-            0,
-            0xFFFFFFFF,
-            1,
-            None,
-            &mut bytes_needed,
-            &mut jobs_count,
-        )
-    };
+        let jobs: &[JOB_INFO_1W] =
+            unsafe { slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_1W, jobs_count) };
+
+        Ok(jobs
+            .iter()
+            .map(|job| PrinterJob::from_platform_printer_job_getters(job))
+            .collect())
+    })
+}
 
-    if first_call_result.is_err() || bytes_needed == 0 {
-        let _ = unsafe { ClosePrinter(printer_handle) };
+/**
+ * Retrieve a range of print jobs of a specific printer with EnumJobsW,
+ * starting at the zero-based queue position `first_job`
+ */
+pub fn enum_printer_jobs_range(
+    printer_name: &str,
+    first_job: u32,
+    jobs_limit: u32,
+) -> Result<Vec<PrinterJob>, &'static str> {
+    if jobs_limit == 0 {
         return Ok(vec![]);
     }
 
-    // Allocate memory based on bytes_needed
-    let mut buffer = vec![0u8; bytes_needed as usize];
+    with_printer(printer_name, |printer_handle| {
+        let Some((buffer, jobs_count)) =
+            enum_jobs_buffer::<JOB_INFO_2W>(printer_handle, first_job, jobs_limit, 2)?
+        else {
+            return Ok(vec![]);
+        };
 
-    // Second call to actually retrieve job info
-    let second_call_result = unsafe {
-        EnumJobsW(
-            printer_handle,
-            0,
-            0xFFFFFFFF,
+        // Convert raw buffer into Vec<JOB_INFO_2W>
+        let jobs: &[JOB_INFO_2W] =
+            unsafe { slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_2W, jobs_count) };
+
+        // The getters copy the strings out, so the jobs stay valid once the buffer is dropped
+        let mut jobs: Vec<PrinterJob> = jobs
+            .iter()
+            .map(|job| PrinterJob::from_platform_printer_job_getters(job))
+            .collect();
+
+        // Chained jobs are only reported by level 3, which carries just the ids
+        if let Ok(Some((buffer, links_count))) =
+            enum_jobs_buffer::<JOB_INFO_3>(printer_handle, first_job, jobs_limit, 3)
+        {
+            let links: &[JOB_INFO_3] =
+                unsafe { slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_3, links_count) };
+            for job in jobs.iter_mut() {
+                job.next_job_id = links
+                    .iter()
+                    .find(|link| link.JobId as u64 == job.id && link.NextJobId != 0)
+                    .map(|link| link.NextJobId as u64);
+            }
+        }
+
+        Ok(jobs)
+    })
+}
+
+/**
+ * Call EnumJobsW at the given level until the buffer is large enough, returning the filled
+ * buffer and the number of `T` entries it holds. Jobs submitted between two calls make the
+ * next one fail again, so it is retried, and failures of any call are reported
+ */
+fn enum_jobs_buffer<T>(
+    printer_handle: &PrinterHandle,
+    first_job: u32,
+    jobs_limit: u32,
+    level: u32,
+) -> Result<Option<(Vec<u8>, usize)>, &'static str> {
+    call_with_buffer(
+        ENUM_JOBS_ATTEMPTS,
+        size_of::<T>(),
+        |buffer, bytes_needed, jobs_count| {
+            sizing_call(unsafe {
+                EnumJobsW(
+                    printer_handle.raw(),
+                    first_job,
+                    jobs_limit,
+                    level,
+                    buffer,
+                    bytes_needed,
+                    jobs_count,
+                )
+            })
+        },
+    )
+    .map_err(|err| match err {
+        SizingError::Failed(_) => "EnumJobsW failed",
+        SizingError::KeptChanging => "EnumJobsW failed, the queue kept changing",
+    })
+}
+
+/**
+ * Change job state
+ */
+pub fn set_job_state(printer_name: &str, command: u64, job_id: u64) -> Result<(), &'static str> {
+    with_printer(printer_name, |printer_guard| {
+        let result = unsafe {
+            SetJobW(
+                printer_guard.raw(),
+                job_id as c_ulong,
+                0,
+                None,
+                command as c_ulong,
+            )
+        };
+
+        if result == BOOL::from(false) {
+            Err("SetJobW failed")
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/**
+ * Check if a job is still in the printer queue with GetJobW
+ */
+pub fn job_exists(printer_name: &str, job_id: u64) -> bool {
+    let Ok(printer_handle) = PrinterHandle::open(printer_name) else {
+        return false;
+    };
+
+    // Unknown job ids fail with ERROR_INVALID_PARAMETER and leave bytes_needed at 0,
+    // while existing jobs fail with ERROR_INSUFFICIENT_BUFFER and report the size
+    let mut bytes_needed: u32 = 0;
+    let _ = unsafe {
+        GetJobW(
+            printer_handle.raw(),
+            job_id as c_ulong,
             1,
-            Some(buffer.as_mut()),
+            None,
             &mut bytes_needed,
-            &mut jobs_count,
         )
     };
 
-    let _ = unsafe { ClosePrinter(printer_handle) };
+    bytes_needed > 0
+}
 
-    if second_call_result.is_err() {
-        return Err("EnumJobsW failed");
+/**
+ * Change job priority with GetJobW and SetJobW at level 2
+ */
+pub fn set_job_priority(
+    printer_name: &str,
+    job_id: u64,
+    priority: u32,
+) -> Result<(), &'static str> {
+    if !(JOB_MIN_PRIORITY..=JOB_MAX_PRIORITY).contains(&priority) {
+        return Err("Job priority out of range (1-99)");
     }
 
-    // Convert raw buffer into Vec<JOB_INFO_1W>
-    let jobs: &[JOB_INFO_1W] = unsafe {
-        slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_1W, jobs_count as usize)
-    };
+    with_printer(printer_name, |printer_guard| unsafe {
+        let printer_handle = printer_guard.raw();
 
-    let jobs: Vec<PrinterJob> = jobs.iter().map(|job| PrinterJob::from_platform_printer_job_getters(job)).collect();
-    Ok(jobs)
-}
+        let mut bytes_needed: u32 = 0;
+        let _ = GetJobW(
+            printer_handle,
+            job_id as c_ulong,
+            2,
+            None,
+            &mut bytes_needed,
+        );
+        if bytes_needed == 0 {
+            return Err("GetJobW failed");
+        }
 
-/**
- * Change job state
- */
-pub fn set_job_state(printer_name: &str, command: u64, job_id: u64) -> Result<(), &'static str> {
-    unsafe {
-        let printer_handle = open_printer(printer_name)?;
-        let printer_handle = PRINTER_HANDLE {
-            Value: printer_handle
-        };
+        let mut buffer = vec![0u8; bytes_needed as usize];
+        let result = GetJobW(
+            printer_handle,
+            job_id as c_ulong,
+            2,
+            Some(buffer.as_mut()),
+            &mut bytes_needed,
+        );
+        if result == BOOL::from(false) {
+            return Err("GetJobW failed");
+        }
+
+        let job_info = &mut *(buffer.as_mut_ptr() as *mut JOB_INFO_2W);
+        job_info.Priority = priority;
+        job_info.Position = JOB_POSITION_UNSPECIFIED;
 
         let result = SetJobW(
             printer_handle,
             job_id as c_ulong,
+            2,
+            Some(buffer.as_ptr()),
             0,
-            None,
-            command as c_ulong,
         );
 
-        let _ = ClosePrinter(printer_handle);
-
         if result == BOOL::from(false) {
             Err("SetJobW failed")
         } else {
             Ok(())
         }
-    }
+    })
 }
@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+use windows::Win32::Foundation::{HANDLE, WAIT_OBJECT_0};
+use windows::Win32::Graphics::Printing::{
+    ClosePrinter, FINDPRINTERCHANGENOTIFICATION_HANDLE, FindClosePrinterChangeNotification,
+    FindFirstPrinterChangeNotification, FindNextPrinterChangeNotification, OpenPrinterW,
+    PRINTER_HANDLE,
+};
+use windows::Win32::System::Threading::WaitForSingleObject;
+use windows::core::PCWSTR;
+
+// Printer added, deleted or changed on the local server (PRINTER_CHANGE_PRINTER)
+const PRINTER_CHANGE_PRINTER: u32 = 0x0000_00FF;
+
+/**
+ * Change notification opened on the local print server
+ */
+struct ServerNotification {
+    server: PRINTER_HANDLE,
+    notification: FINDPRINTERCHANGENOTIFICATION_HANDLE,
+}
+
+// The handles are only used while the mutex is locked
+unsafe impl Send for ServerNotification {}
+
+/**
+ * Open notification and number of changes seen since the process started
+ */
+static SERVER_NOTIFICATION: Mutex<(Option<ServerNotification>, u64)> = Mutex::new((None, 0));
+
+impl ServerNotification {
+    fn open() -> Option<Self> {
+        let mut server = PRINTER_HANDLE::default();
+        unsafe { OpenPrinterW(PCWSTR::null(), &mut server, None) }.ok()?;
+
+        match unsafe { FindFirstPrinterChangeNotification(server, PRINTER_CHANGE_PRINTER, 0, None) }
+        {
+            Ok(notification) => Some(ServerNotification {
+                server,
+                notification,
+            }),
+            Err(_) => {
+                let _ = unsafe { ClosePrinter(server) };
+                None
+            }
+        }
+    }
+
+    /**
+     * Return how many notifications were signaled since the last call, without waiting,
+     * or None if the notification stopped working
+     */
+    fn poll(&self) -> Option<u64> {
+        let event = HANDLE(self.notification.0);
+        let mut changes = 0;
+        while unsafe { WaitForSingleObject(event, 0) } == WAIT_OBJECT_0 {
+            let mut change = 0u32;
+            // Resets the notification for the next change
+            let reset = unsafe {
+                FindNextPrinterChangeNotification(self.notification, Some(&mut change), None, None)
+            };
+            if !reset.as_bool() {
+                return None;
+            }
+            changes += 1;
+        }
+        Some(changes)
+    }
+}
+
+impl Drop for ServerNotification {
+    fn drop(&mut self) {
+        let _ = unsafe { FindClosePrinterChangeNotification(self.notification) };
+        let _ = unsafe { ClosePrinter(self.server) };
+    }
+}
+
+/**
+ * Return a counter that changes every time a printer is added, deleted or changed
+ * on the local print server, None if the spooler does not deliver change notifications
+ */
+pub fn printers_change_cookie() -> Option<u64> {
+    let mut state = SERVER_NOTIFICATION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let (notification, changes) = &mut *state;
+
+    if notification.is_none() {
+        *notification = ServerNotification::open();
+        // Changes made while no notification was open were missed
+        *changes += 1;
+    }
+
+    match notification.as_ref()?.poll() {
+        Some(count) => {
+            *changes += count;
+            Some(*changes)
+        }
+        None => {
+            *notification = None;
+            None
+        }
+    }
+}
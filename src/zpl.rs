@@ -0,0 +1,149 @@
+//! Build ZPL (Zebra Programming Language) labels as bytes ready to be printed as RAW,
+//! e.g. with `LabelContent::Raw` or `DocumentFormat::Raw`
+
+use image::DynamicImage;
+
+const MAX_QR_MAGNIFICATION: u32 = 10;
+
+/**
+ * Scalable or bitmap printer font, selected with ^A
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZplFont {
+    /**
+     * Font name, '0' is the scalable font available on every Zebra printer
+     */
+    pub name: char,
+    /**
+     * Character height in dots
+     */
+    pub height: u32,
+    /**
+     * Character width in dots
+     */
+    pub width: u32,
+}
+
+impl Default for ZplFont {
+    fn default() -> Self {
+        ZplFont {
+            name: '0',
+            height: 30,
+            width: 30,
+        }
+    }
+}
+
+/**
+ * Builder of a ZPL document, positions are in dots from the top left corner of the label
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Zpl {
+    buffer: Vec<u8>,
+}
+
+impl Zpl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Start a label (^XA), with field data read as UTF-8 (^CI28)
+     */
+    pub fn start(self) -> Self {
+        self.command("^XA^CI28")
+    }
+
+    /**
+     * End the label (^XZ), which makes the printer print it
+     */
+    pub fn end(self) -> Self {
+        self.command("^XZ")
+    }
+
+    /**
+     * Print a line of text
+     */
+    pub fn text(self, x: u32, y: u32, font: ZplFont, value: &str) -> Self {
+        let command = format!("^FO{x},{y}^A{}N,{},{}", font.name, font.height, font.width);
+        self.command(&command).field_data(value.as_bytes())
+    }
+
+    /**
+     * Print a Code 128 barcode of `height` dots with its value written below
+     */
+    pub fn barcode128(self, x: u32, y: u32, height: u32, value: &str) -> Self {
+        self.command(&format!("^FO{x},{y}^BCN,{height},Y,N,N"))
+            .field_data(value.as_bytes())
+    }
+
+    /**
+     * Print a QR code, `magnification` (1 to 10) is the size in dots of each module
+     */
+    pub fn qr(self, x: u32, y: u32, magnification: u32, value: &str) -> Self {
+        let magnification = magnification.clamp(1, MAX_QR_MAGNIFICATION);
+        // QA: error correction level Q, automatic input mode
+        let mut data = b"QA,".to_vec();
+        data.extend_from_slice(value.as_bytes());
+        self.command(&format!("^FO{x},{y}^BQN,2,{magnification}"))
+            .field_data(&data)
+    }
+
+    /**
+     * Print an image as a monochrome ^GF graphic, pixels darker than mid gray are black
+     */
+    pub fn graphic(self, x: u32, y: u32, image: &DynamicImage) -> Self {
+        let luma = image.to_luma8();
+        let bytes_per_row = luma.width().div_ceil(8) as usize;
+        let total_bytes = bytes_per_row * luma.height() as usize;
+
+        let mut hex = String::with_capacity(total_bytes * 2);
+        for row in luma.rows() {
+            let mut bits = vec![0u8; bytes_per_row];
+            for (column, pixel) in row.enumerate() {
+                if pixel.0[0] < 128 {
+                    bits[column / 8] |= 0x80 >> (column % 8);
+                }
+            }
+            for byte in bits {
+                hex.push_str(&format!("{byte:02X}"));
+            }
+        }
+
+        self.command(&format!(
+            "^FO{x},{y}^GFA,{total_bytes},{total_bytes},{bytes_per_row},{hex}^FS"
+        ))
+    }
+
+    /**
+     * Append raw ZPL for commands without a builder
+     */
+    pub fn command(mut self, command: &str) -> Self {
+        self.buffer.extend_from_slice(command.as_bytes());
+        self
+    }
+
+    /**
+     * Return the generated document
+     */
+    pub fn build(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /**
+     * Append field data with ^FH so the ^, ~ and _ characters cannot be read as commands
+     */
+    fn field_data(mut self, data: &[u8]) -> Self {
+        self.buffer.extend_from_slice(b"^FH^FD");
+        for byte in data {
+            match byte {
+                b'^' | b'~' | b'_' => self
+                    .buffer
+                    .extend_from_slice(format!("_{byte:02X}").as_bytes()),
+                _ => self.buffer.push(*byte),
+            }
+        }
+        self.buffer.extend_from_slice(b"^FS");
+        self
+    }
+}
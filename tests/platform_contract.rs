@@ -0,0 +1,176 @@
+//! Contracts every backend must keep, checked against the printers of the machine running
+//! the tests. Only read-only calls are made, so running it never prints nor changes a queue.
+//! Printer specific tests pass without checking when no printer is installed (like on CI)
+
+use printers::common::base::job::{JobInfoLevel, PrinterJobState};
+use printers::{PRINTER_NOT_FOUND, Printer};
+
+const MISSING_PRINTER: &str = "printers-contract-test-missing-printer";
+
+fn printers_or_skip(test: &str) -> Vec<Printer> {
+    let printers = printers::get_printers();
+    if printers.is_empty() {
+        eprintln!("{test}: skipped, no printers available");
+    }
+    printers
+}
+
+#[test]
+fn supported_features_match_is_supported() {
+    let features = printers::supported_features();
+    assert_eq!(printers::is_supported(), features.printing);
+
+    // Every other feature builds on being able to reach printers
+    if !features.printing {
+        assert_eq!(features, Default::default());
+    }
+}
+
+#[test]
+fn printer_listings_agree() {
+    let printers = printers::get_printers();
+    let mut names: Vec<&str> = printers.iter().map(|p| p.system_name.as_str()).collect();
+    names.sort_unstable();
+
+    if let Ok(listed) = printers::try_get_printers() {
+        assert_eq!(listed.len(), printers.len());
+    }
+
+    let sorted = printers::get_printers_sorted();
+    let mut sorted_names: Vec<&str> = sorted.iter().map(|p| p.system_name.as_str()).collect();
+    sorted_names.sort_unstable();
+    assert_eq!(sorted_names, names);
+
+    if let Some(default) = printers::get_default_printer() {
+        assert!(names.contains(&default.system_name.as_str()));
+        assert_eq!(sorted[0].system_name, default.system_name);
+    }
+
+    let caps = printers::get_all_caps();
+    assert_eq!(caps.len(), printers.len());
+    assert!(names.iter().all(|name| caps.contains_key(*name)));
+}
+
+#[test]
+fn missing_printer_is_reported_as_not_found() {
+    assert!(printers::get_printer_by_name(MISSING_PRINTER).is_none());
+    assert!(printers::get_printer_by_exact_name(MISSING_PRINTER).is_none());
+    assert!(printers::get_printer_status(MISSING_PRINTER).is_none());
+    assert_eq!(
+        printers::get_printer_permissions(MISSING_PRINTER),
+        Err(PRINTER_NOT_FOUND)
+    );
+    assert_eq!(
+        printers::set_printer_shared(MISSING_PRINTER, true, None),
+        Err(PRINTER_NOT_FOUND)
+    );
+}
+
+#[test]
+fn printers_are_found_by_their_names() {
+    for printer in printers_or_skip("printers_are_found_by_their_names") {
+        assert!(!printer.system_name.is_empty());
+        assert!(printer.matches_name(&printer.system_name));
+
+        let found = printers::get_printer_by_exact_name(&printer.system_name)
+            .unwrap_or_else(|| panic!("{} is listed but not found", printer.system_name));
+        assert_eq!(found.system_name, printer.system_name);
+        assert!(printers::get_printer_by_name(&printer.system_name).is_some());
+    }
+}
+
+#[test]
+fn device_caps_are_consistent() {
+    for printer in printers_or_skip("device_caps_are_consistent") {
+        let caps = printer.get_printer_caps();
+        let name = &printer.system_name;
+
+        for margin in [
+            caps.margin_top,
+            caps.margin_right,
+            caps.margin_bottom,
+            caps.margin_left,
+        ] {
+            assert!(margin >= 0, "{name}: negative margin {caps:?}");
+        }
+        if caps.page_width > 0 {
+            assert_eq!(
+                caps.margin_left + caps.print_table_width + caps.margin_right,
+                caps.page_width,
+                "{name}: {caps:?}"
+            );
+        }
+        if caps.page_height > 0 {
+            assert_eq!(
+                caps.margin_top + caps.print_table_height + caps.margin_bottom,
+                caps.page_height,
+                "{name}: {caps:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn job_listings_are_consistent() {
+    for printer in printers_or_skip("job_listings_are_consistent") {
+        let name = &printer.system_name;
+
+        for job in printer.get_active_jobs() {
+            assert!(
+                !job.state.is_terminal(),
+                "{name}: active job {} is {}",
+                job.id,
+                job.state
+            );
+            assert_ne!(
+                job.state,
+                PrinterJobState::UNKNOWN,
+                "{name}: job {}",
+                job.id
+            );
+        }
+
+        for level in [JobInfoLevel::Basic, JobInfoLevel::Detailed] {
+            let _ = printer.get_jobs_level(false, level);
+        }
+
+        assert!(printer.get_jobs_paged(0, 0).is_empty());
+        assert!(printer.get_jobs_paged(0, 1).len() <= 1);
+        assert!(printer.get_jobs_paged(u32::MAX, 10).is_empty());
+    }
+}
+
+#[cfg(target_family = "windows")]
+#[test]
+fn repeated_job_enumeration_is_consistent() {
+    // Every listing goes through the EnumJobsW sizing calls, repeated so a queue changing
+    // between them is hit on busy machines
+    for printer in printers_or_skip("repeated_job_enumeration_is_consistent") {
+        let name = &printer.system_name;
+
+        for _ in 0..100 {
+            for level in [JobInfoLevel::Basic, JobInfoLevel::Detailed] {
+                let jobs = printer.get_jobs_level(false, level);
+                let mut ids: Vec<u64> = jobs.iter().map(|j| j.id).collect();
+                ids.sort_unstable();
+                ids.dedup();
+
+                assert_eq!(ids.len(), jobs.len(), "{name}: duplicated jobs");
+                assert!(ids.iter().all(|id| *id != 0), "{name}: job without id");
+            }
+        }
+    }
+}
+
+#[test]
+fn unsupported_job_control_is_an_error() {
+    if printers::supported_features().job_control {
+        return;
+    }
+
+    for printer in printers_or_skip("unsupported_job_control_is_an_error") {
+        assert!(printer.pause_job(1).is_err());
+        assert!(printer.resume_job(1).is_err());
+        assert!(printer.cancel_job(1).is_err());
+    }
+}
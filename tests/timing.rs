@@ -0,0 +1,65 @@
+//! Timings of the hot paths, ignored by default since they depend on the machine and its
+//! printers. Run them with: cargo test --release --test timing -- --ignored --nocapture
+//! Printing timings submit to the printer named by PRINTERS_TIMING_PRINTER, their jobs are
+//! held so nothing prints, then cancelled
+
+use printers::PrinterJobOptions;
+use std::time::{Duration, Instant};
+
+fn time(runs: u32, mut f: impl FnMut()) -> Duration {
+    let started = Instant::now();
+    for _ in 0..runs {
+        f();
+    }
+    started.elapsed() / runs
+}
+
+#[test]
+#[ignore]
+fn default_printer_lookup() {
+    let runs = 200;
+    if printers::refresh_default().is_none() {
+        eprintln!("default_printer_lookup: skipped, no default printer");
+        return;
+    }
+
+    let cached = time(runs, || {
+        let _ = printers::get_default_printer();
+    });
+    let enumerated = time(runs, || {
+        let _ = printers::refresh_default();
+    });
+
+    println!("get_default_printer: {cached:?} per call cached, {enumerated:?} enumerating");
+}
+
+#[test]
+#[ignore]
+fn session_batch_throughput() {
+    let Ok(name) = std::env::var("PRINTERS_TIMING_PRINTER") else {
+        eprintln!("session_batch_throughput: skipped, PRINTERS_TIMING_PRINTER is not set");
+        return;
+    };
+    let printer = printers::get_printer_by_name(&name).expect("timing printer not found");
+    let options = PrinterJobOptions {
+        name: Some("printers timing job"),
+        raw_properties: &[("hold", "true")],
+        ..PrinterJobOptions::none()
+    };
+    let (jobs, buffer) = (50, b"printers timing job\n");
+    let mut job_ids = vec![];
+
+    let stateless = time(jobs, || {
+        job_ids.push(printer.print(buffer, options).unwrap().job_id);
+    });
+    let session = printer.open_session().unwrap();
+    let batched = time(jobs, || {
+        job_ids.push(session.print(buffer, options).unwrap().job_id);
+    });
+    drop(session);
+
+    for job_id in job_ids {
+        let _ = printer.cancel_job(job_id);
+    }
+    println!("print: {stateless:?} per job, PrintSession::print: {batched:?} per job");
+}